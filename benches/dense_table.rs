@@ -0,0 +1,58 @@
+//! Compare the throughput of the dense table of the DFA with searching the ranges of every state, the layout it
+//! replaced. Run it with `cargo bench --bench dense_table`.
+
+use std::time::{Duration, Instant};
+
+use regex::regex::Regex;
+
+fn main() {
+    let ascii = "The quick brown fox jumps over the lazy dog 0123456789 ".repeat(20_000);
+    let unicode = "Ünïcödé wörds, Ελληνικά и кириллица 😀 ".repeat(20_000);
+    // The ASCII text followed by the Unicode one, it reaches the dead state in the middle of the text
+    let mixed = format!("{ascii}{unicode}");
+    let regexes = [
+        "(?s).*",
+        "(?s).*(9|😀) ",
+        "[\\w\\s,😀]*",
+        "((\\w|😀)+[\\s,]+)*",
+        // They don't match: the search reaches the dead state at the first characters, in the middle of the
+        // mixed text, or never and reads all of it
+        "[a-z ]*",
+        "[ -~]*",
+        "(?s).*#",
+    ];
+
+    for regex in regexes {
+        let re = Regex::new(regex).unwrap();
+        let sparse_dfa = re.sparse_dfa().unwrap();
+
+        for (name, text) in [("ascii", &ascii), ("unicode", &unicode), ("mixed", &mixed)] {
+            let (sparse, sparse_match) = time(|| sparse_dfa.validate(text.chars().map(u32::from)));
+            let (dense, dense_match) = time(|| re.is_match(text));
+            assert_eq!(sparse_match, dense_match);
+
+            let throughput = |duration: Duration| text.len() as f64 / duration.as_secs_f64() / 1e6;
+            // The throughput counts the whole text, even when the search stops at the dead state before its end
+            println!(
+                "{regex:24} {name:8} sparse {:8.1} MB/s {:>10.1?}  dense {:8.1} MB/s {:>10.1?}  {:.1}x",
+                throughput(sparse),
+                sparse,
+                throughput(dense),
+                dense,
+                sparse.as_secs_f64() / dense.as_secs_f64()
+            );
+        }
+    }
+}
+
+/// The best time of a few runs
+fn time(run: impl Fn() -> bool) -> (Duration, bool) {
+    (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            let result = std::hint::black_box(run());
+            (start.elapsed(), result)
+        })
+        .min()
+        .unwrap()
+}
@@ -0,0 +1,169 @@
+#!/usr/bin/env perl
+# Generate the Unicode tables in src/regex/unicode_tables from the Unicode Character Database shipped with Perl.
+#
+# Usage: perl scripts/generate_unicode_tables.pl
+use strict;
+use warnings;
+
+use File::Basename qw(dirname);
+use Unicode::UCD qw(all_casefolds prop_invlist prop_value_aliases prop_values);
+
+my $out_dir = dirname(__FILE__) . "/../src/regex/unicode_tables";
+my $unicode_version = Unicode::UCD::UnicodeVersion();
+
+# Convert an inversion list into inclusive ranges of code points, skipping the surrogates that aren't chars
+sub ranges {
+    my @invlist = @_;
+    my @ranges;
+
+    for (my $i = 0; $i < @invlist; $i += 2) {
+        my $start = $invlist[$i];
+        my $end = $i + 1 < @invlist ? $invlist[$i + 1] - 1 : 0x10FFFF;
+
+        for my $range (split_surrogates($start, $end)) {
+            push @ranges, $range;
+        }
+    }
+
+    return merge(@ranges);
+}
+
+sub split_surrogates {
+    my ($start, $end) = @_;
+    my @ranges;
+
+    push @ranges, [$start, $end < 0xD7FF ? $end : 0xD7FF] if $start <= 0xD7FF;
+    push @ranges, [$start > 0xE000 ? $start : 0xE000, $end] if $end >= 0xE000;
+
+    return @ranges;
+}
+
+sub merge {
+    my @ranges = sort { $a->[0] <=> $b->[0] } @_;
+    my @merged;
+
+    for my $range (@ranges) {
+        if (@merged && $range->[0] <= $merged[-1][1] + 1) {
+            $merged[-1][1] = $range->[1] if $range->[1] > $merged[-1][1];
+        } else {
+            push @merged, [@$range];
+        }
+    }
+
+    return @merged;
+}
+
+sub property_ranges {
+    my ($property) = @_;
+    my @invlist = prop_invlist($property);
+    die "Unknown property $property" unless @invlist;
+
+    return ranges(@invlist);
+}
+
+sub format_table {
+    my ($name, @ranges) = @_;
+    return "pub const $name: &[(char, char)] = &[];\n" unless @ranges;
+
+    my @items = map { sprintf("('\\u{%X}', '\\u{%X}')", $_->[0], $_->[1]) } @ranges;
+
+    # Short tables are kept on one line, the same way rustfmt lays out arrays narrower than its `array_width`
+    my $array = "&[" . join(", ", @items) . "]";
+    my $line = "pub const $name: &[(char, char)] = $array;";
+    return "$line\n" if length($array) <= 72 && length($line) <= 120;
+
+    return "pub const $name: &[(char, char)] = &[\n" . join("", map { "    $_,\n" } @items) . "];\n";
+}
+
+# The names are compared ignoring case, spaces, "_" and "-", following UAX #44 LM3
+sub normalize {
+    my ($name) = @_;
+    $name = lc($name);
+    $name =~ s/[ _-]//g;
+
+    return $name;
+}
+
+# Write a module with a table for every value of a property, `BY_NAME` maps the long name of every value to its
+# table and `ALIASES` maps every normalized alias of a value to its long name
+sub write_property_module {
+    my ($module, $property) = @_;
+    my (%tables, %aliases);
+
+    for my $value (prop_values($property)) {
+        # Perl doesn't know the aliases of a few values without characters, their name is used as is
+        my @names = prop_value_aliases($property, $value);
+        @names = ($value) unless @names;
+        my $long_name = $names[1] // $names[0];
+
+        # Some values don't have any character, e.g. the Katakana_Or_Hiragana script
+        $tables{$long_name} = [ranges(prop_invlist("$property=$value"))];
+        $aliases{normalize($_)} = $long_name for @names;
+    }
+
+    my $content = "pub const BY_NAME: &[(&str, &[(char, char)])] = &[\n";
+    $content .= sprintf("    (\"%s\", %s),\n", $_, uc($_)) for sort keys %tables;
+    $content .= "];\n\npub const ALIASES: &[(&str, &str)] = &[\n";
+    $content .= sprintf("    (\"%s\", \"%s\"),\n", $_, $aliases{$_}) for sort keys %aliases;
+    $content .= "];\n";
+    $content .= "\n" . format_table(uc($_), @{$tables{$_}}) for sort keys %tables;
+
+    write_module($module, $content);
+}
+
+# Write the table that maps every character with a simple case folding to all the other characters that fold to
+# the same character, e.g. "k" to "K" and the Kelvin sign
+sub write_case_folding_module {
+    my $casefolds = all_casefolds();
+    my %orbits;
+
+    for my $code_point (keys %$casefolds) {
+        my $simple = $casefolds->{$code_point}{simple};
+        next unless length $simple;
+
+        my $folded = hex($simple);
+        $orbits{$folded}{$folded} = 1;
+        $orbits{$folded}{$code_point} = 1;
+    }
+
+    my %equivalents;
+    for my $orbit (values %orbits) {
+        my @members = sort { $a <=> $b } keys %$orbit;
+        for my $member (@members) {
+            $equivalents{$member} = [grep { $_ != $member } @members];
+        }
+    }
+
+    my $content = "pub const CASE_FOLDING_SIMPLE: &[(char, &[char])] = &[\n";
+    for my $code_point (sort { $a <=> $b } keys %equivalents) {
+        my $others = join(", ", map { sprintf("'\\u{%X}'", $_) } @{$equivalents{$code_point}});
+        $content .= sprintf("    ('\\u{%X}', &[%s]),\n", $code_point, $others);
+    }
+    $content .= "];\n";
+
+    write_module("case_folding", $content);
+}
+
+sub write_module {
+    my ($module, $content) = @_;
+    my $path = "$out_dir/$module.rs";
+
+    open(my $file, '>', $path) or die "Can't write $path: $!";
+    print $file "// DO NOT EDIT: generated by scripts/generate_unicode_tables.pl from Unicode $unicode_version\n\n";
+    print $file $content;
+    close($file);
+}
+
+my ($major, $minor, $patch) = split(/\./, $unicode_version);
+write_module("version", "/// The version of the Unicode standard the tables are generated from\npub const UNICODE_VERSION: (u8, u8, u8) = ($major, $minor, $patch);\n");
+
+write_property_module("general_category", "gc");
+write_property_module("script", "sc");
+
+write_case_folding_module();
+
+# \d, \s and \w as defined by UTS #18 Annex C
+write_module("perl_decimal", format_table("DECIMAL_NUMBER", property_ranges("gc=Nd")));
+write_module("perl_space", format_table("WHITE_SPACE", property_ranges("White_Space")));
+my @perl_word = merge(map { property_ranges($_) } qw(Alphabetic gc=M gc=Nd gc=Pc Join_Control));
+write_module("perl_word", format_table("PERL_WORD", @perl_word));
@@ -1,70 +1,175 @@
 use std::collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap};
 
 use super::debug::AutomataDebug;
-use super::helper::calculate_hash;
+use super::dense::DenseDfa;
+use super::interval::{partition, IntervalSet, CODE_POINT_END};
+use super::lazy::LazyDfa;
+use super::look::Look;
+use super::parser::Assertion;
 use super::parser::CharacterClassBinaryOp;
 use super::parser::CharacterClassType;
-use super::parser::{BinaryOp, RegexAST, UnaryOp};
+use super::parser::{BinaryOp, Error, RegexAST, UnaryOp};
+use super::utf8::utf8_sequences;
 
 #[derive(Eq, Hash, PartialEq, Debug, Clone, Copy, Ord, PartialOrd)]
 pub enum TransitionType {
     Epsilon,
-    Symbol(char),
-    AnyCharacter,
+    /// Matches the characters in the half-open interval of code points `[start, end)`
+    Range(u32, u32),
+    /// An epsilon transition that can only be taken where the assertion holds
+    Assertion(Assertion),
+    /// Matches the bytes in the half-open interval `[start, end)`, it is only used by the byte automata and
+    /// `utf8_automata` replaces it with a `Range` of bytes
+    Bytes(u32, u32),
+}
+
+impl TransitionType {
+    /// A transition that only matches `symbol`
+    pub fn symbol(symbol: char) -> Self {
+        Self::range(symbol, symbol)
+    }
+
+    /// A transition that matches the characters between `start` and `end` inclusive
+    pub fn range(start: char, end: char) -> Self {
+        TransitionType::Range(start as u32, end as u32 + 1)
+    }
+
+    /// A transition that matches every character, `.`
+    pub fn any_character() -> Self {
+        TransitionType::Range(0, CODE_POINT_END)
+    }
 }
 
 pub type State = usize;
 
+/// The default for the most states of the NFA built from a regex
+pub const DEFAULT_NFA_SIZE_LIMIT: usize = 100_000;
+/// The default for the most states of every DFA built by the subset construction, past it the NFA is simulated
+/// instead
+pub const DEFAULT_DFA_SIZE_LIMIT: usize = 10_000;
+
+/// The biggest automata that can be built from a regex, so a pattern can't exhaust the memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeLimits {
+    /// The most states of the NFA built from the regex
+    pub nfa_states: usize,
+    /// The most states of every DFA built by the subset construction, past it the NFA is simulated instead
+    pub dfa_states: usize,
+}
+
+/// The algorithm that minimizes the DFA
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Minimization {
+    /// Brzozowski's algorithm, determinize the reversed NFA twice. It is simple, but the first subset
+    /// construction runs on the reversed NFA, that can have exponentially many states even when the DFA doesn't.
+    #[default]
+    Brzozowski,
+    /// The subset construction followed by Hopcroft's partition refinement, in O(n log n) of the DFA states
+    Hopcroft,
+}
+
+/// The engine that checks if a text is matched by the whole regex
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    /// The minimized DFA, it reads every character in constant time
+    Dfa,
+    /// The simulation of the NFA, used when the DFA would have too many states. It follows all the NFA states
+    /// that can be reached at the same time, so reading a character is linear in the size of the NFA.
+    Nfa,
+    /// The DFA built while matching, only the states reached by the texts are created and they are cached
+    LazyDfa,
+}
+
+/// The automaton built from a regex by [`Automata::from_regex_expr`] or [`LazyDfa::from_regex_expr`]
+#[derive(Debug, Clone)]
+pub enum Matcher {
+    Dfa(Dfa),
+    /// An NFA without assertion transitions
+    Nfa(Automata),
+    LazyDfa(LazyDfa),
+}
+
+impl Matcher {
+    pub fn engine(&self) -> Engine {
+        match self {
+            Matcher::Dfa(_) => Engine::Dfa,
+            Matcher::Nfa(_) => Engine::Nfa,
+            Matcher::LazyDfa(_) => Engine::LazyDfa,
+        }
+    }
+
+    pub fn validate_str(&self, text: &str) -> bool {
+        self.validate(text.chars().map(u32::from))
+    }
+
+    /// Check if the whole `text` is accepted by an automaton built by `utf8_automata`
+    pub fn validate_bytes(&self, text: &[u8]) -> bool {
+        self.validate(text.iter().map(|&byte| u32::from(byte)))
+    }
+
+    fn validate(&self, symbols: impl Iterator<Item = u32>) -> bool {
+        match self {
+            Matcher::Dfa(dfa) => dfa.validate(symbols),
+            Matcher::Nfa(nfa) => nfa.validate(symbols),
+            Matcher::LazyDfa(lazy_dfa) => lazy_dfa.validate(symbols),
+        }
+    }
+}
+
+impl Default for SizeLimits {
+    fn default() -> Self {
+        Self {
+            nfa_states: DEFAULT_NFA_SIZE_LIMIT,
+            dfa_states: DEFAULT_DFA_SIZE_LIMIT,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Dfa {
-    start_state: State,
-    final_states: BTreeSet<State>,
-    transitions: BTreeMap<(State, TransitionType), State>,
+    /// The transitions as a table, the states are its rows
+    dense: DenseDfa,
 }
 
 impl Dfa {
-    fn next_state(&self, state: State, transition: TransitionType) -> Option<State> {
-        self.transitions.get(&(state, transition)).copied()
+    pub fn dense(&self) -> &DenseDfa {
+        &self.dense
     }
 
     pub fn validate_str(&self, text: &str) -> bool {
-        let mut state = Some(self.start_state);
-
-        for char in text.chars() {
-            if let Some(curr_state) = state {
-                state = self
-                    .next_state(curr_state, TransitionType::Symbol(char))
-                    .map_or_else(|| self.next_state(curr_state, TransitionType::AnyCharacter), Some);
-            } else {
-                break;
-            }
-        }
+        self.validate(text.chars().map(u32::from))
+    }
 
-        state.map_or(false, |s| self.final_states.contains(&s))
+    /// Check if the whole sequence of code points, or of bytes for the byte automata, is accepted
+    pub fn validate(&self, symbols: impl Iterator<Item = u32>) -> bool {
+        self.dense.validate(symbols)
     }
 }
 
+/// The states are the rows of the dense table, and the dead state is left out with the transitions to it
 impl AutomataDebug for Dfa {
     fn start_state(&self) -> State {
-        self.start_state
+        self.dense.start_row()
     }
 
-    fn final_states(&self) -> &BTreeSet<State> {
-        &self.final_states
+    fn final_states(&self) -> BTreeSet<State> {
+        self.dense.final_rows()
     }
 
     fn states(&self) -> BTreeSet<State> {
-        self.transitions
-            .iter()
-            .map(|((state, _), _)| *state)
-            .chain(self.transitions.values().copied())
+        let transitions = self.dense.range_transitions();
+        transitions
+            .keys()
+            .map(|(state, _)| *state)
+            .chain(transitions.values().copied())
             .collect()
     }
 
     fn transitions(&self) -> BTreeMap<(usize, TransitionType), BTreeSet<State>> {
-        self.transitions
-            .iter()
-            .map(|(&state_transition, &dest_state)| (state_transition, BTreeSet::from([dest_state])))
+        self.dense
+            .range_transitions()
+            .into_iter()
+            .map(|(state_transition, dest_state)| (state_transition, BTreeSet::from([dest_state])))
             .collect()
     }
 }
@@ -91,8 +196,8 @@ impl AutomataDebug for Automata {
         self.start_state
     }
 
-    fn final_states(&self) -> &BTreeSet<State> {
-        &self.final_states
+    fn final_states(&self) -> BTreeSet<State> {
+        self.final_states.clone()
     }
 
     fn states(&self) -> BTreeSet<State> {
@@ -114,11 +219,10 @@ impl Automata {
     }
 
     pub fn eclosure(&self, states: BTreeSet<State>) -> BTreeSet<State> {
-        let mut work: Vec<State> = Vec::from_iter(states.into_iter());
+        let mut work: Vec<State> = Vec::from_iter(states);
         let mut out: BTreeSet<State> = BTreeSet::new();
 
-        while !work.is_empty() {
-            let state = work.pop().unwrap();
+        while let Some(state) = work.pop() {
             out.insert(state);
 
             if let Some(dest_states) = self.next_state(state, TransitionType::Epsilon) {
@@ -133,48 +237,157 @@ impl Automata {
         out
     }
 
-    fn delta(&self, states: &BTreeSet<State>, transition: TransitionType) -> BTreeSet<State> {
-        states
-            .iter()
-            .filter_map(|state| self.next_state(*state, transition))
-            .flatten()
-            .copied()
-            .collect()
+    /// The states reached from `states` by reading every letter of the sorted `alphabet`, in the same order.
+    ///
+    /// Every range is the union of consecutive letters of the alphabet, so instead of checking every transition
+    /// against every letter, each transition adds its destinations to the letters it covers.
+    fn delta(&self, states: &BTreeSet<State>, alphabet: &[TransitionType]) -> Vec<BTreeSet<State>> {
+        let mut dest_states_by_letter = vec![BTreeSet::new(); alphabet.len()];
+
+        let transitions = states.iter().flat_map(|&state| {
+            self.transitions
+                .range((state, TransitionType::Epsilon)..(state + 1, TransitionType::Epsilon))
+        });
+        for (&(_, transition), dest_states) in transitions {
+            let TransitionType::Range(start, end) = transition else {
+                continue;
+            };
+
+            let first_letter = alphabet.partition_point(|&letter| letter < TransitionType::Range(start, start));
+            for (letter, letter_dest_states) in alphabet[first_letter..]
+                .iter()
+                .zip(&mut dest_states_by_letter[first_letter..])
+            {
+                match letter {
+                    TransitionType::Range(_, letter_end) if *letter_end <= end => {
+                        letter_dest_states.extend(dest_states)
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        dest_states_by_letter
     }
 
     fn merge_automata(&mut self, automata: Automata) {
         self.final_states.extend(automata.final_states);
         // TODO: check for a better way to merge two HashMaps
         for ((state, symbol), dest_states) in automata.transitions {
-            self.transitions
-                .entry((state, symbol))
-                .or_insert_with(BTreeSet::new)
-                .extend(dest_states);
+            self.transitions.entry((state, symbol)).or_default().extend(dest_states);
+        }
+    }
+
+    /// Build the DFA of the regex, or the NFA to simulate if the subset construction exceeds the DFA size limit.
+    /// Fails if the NFA exceeds its size limit.
+    pub fn from_regex_expr(expr: RegexAST, limits: SizeLimits, minimization: Minimization) -> Result<Matcher, Error> {
+        Self::nfa_from_regex_expr(expr, limits.nfa_states)?.into_matcher(limits.dfa_states, minimization)
+    }
+
+    /// Build the DFA of the NFA, or keep the NFA to simulate if the subset construction exceeds the DFA size limit
+    pub fn into_matcher(self, dfa_size_limit: usize, minimization: Minimization) -> Result<Matcher, Error> {
+        match self.clone().convert_to_dfa(dfa_size_limit, minimization) {
+            Ok(dfa) => Ok(Matcher::Dfa(dfa)),
+            // The subset construction can create exponentially many states, e.g. for `(a|b)*a(a|b){20}`, so past the
+            // limit it is stopped and the NFA is simulated instead
+            Err(Error::TooBig(_)) => Ok(Matcher::Nfa(self)),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Build the NFA of the regex without assertion transitions. Fails if the NFA exceeds its size limit.
+    pub fn nfa_from_regex_expr(expr: RegexAST, nfa_size_limit: usize) -> Result<Automata, Error> {
+        let automata = build_automata_from_ast(expr, &mut 0, nfa_size_limit)?;
+
+        if automata.has_assertions() {
+            Ok(resolve_assertions(automata))
+        } else {
+            Ok(automata)
         }
     }
 
-    pub fn from_regex_expr(expr: RegexAST) -> Dfa {
-        build_automata_from_ast(expr, &mut 0).convert_to_dfa()
+    /// Check if the whole `text` is accepted by simulating the NFA, following all the states that can be reached
+    /// at the same time. The NFA must not have assertion transitions.
+    pub fn validate_str(&self, text: &str) -> bool {
+        self.validate(text.chars().map(u32::from))
+    }
+
+    /// Like [`Automata::validate_str`], for a sequence of code points, or of bytes for the byte automata
+    pub fn validate(&self, symbols: impl Iterator<Item = u32>) -> bool {
+        let mut states = self.eclosure(BTreeSet::from([self.start_state]));
+
+        for symbol in symbols {
+            if states.is_empty() {
+                return false;
+            }
+            states = self.eclosure(self.step(&states, symbol));
+        }
+
+        !(&states & &self.final_states).is_empty()
+    }
+
+    /// The states reached from `states` by reading the character `code_point`, without following the epsilon
+    /// transitions
+    pub fn step(&self, states: &BTreeSet<State>, code_point: u32) -> BTreeSet<State> {
+        states
+            .iter()
+            .flat_map(|&state| {
+                self.transitions
+                    .range((state, TransitionType::Epsilon)..(state + 1, TransitionType::Epsilon))
+            })
+            .filter(|(&(_, transition), _)| {
+                matches!(transition, TransitionType::Range(start, end) if start <= code_point && code_point < end)
+            })
+            .flat_map(|(_, dest_states)| dest_states.iter().copied())
+            .collect()
     }
 
-    /// Produce a minimized DFA using Brzozowski’s Algorithm.
+    fn has_assertions(&self) -> bool {
+        self.transitions
+            .keys()
+            .any(|(_, transition)| matches!(transition, TransitionType::Assertion(_)))
+    }
+
+    /// Produce a minimized DFA using Brzozowski’s or Hopcroft's Algorithm.
     /// Reference: Engineering: A Compiler 2nd edition (Cooper, Keith D., Torczon, Linda),
-    /// Chapter 2.6.2
-    fn convert_to_dfa(self) -> Dfa {
-        let nfa = reachable(subset(reverse(reachable(subset(reverse(self))))));
-
-        Dfa {
-            start_state: nfa.start_state(),
-            transitions: nfa
-                .transitions
-                .iter()
-                .map(|(state_transition_types, dest_states)| {
-                    // At this point the NFA only have one state in it's dest states
-                    (*state_transition_types, dest_states.iter().next().copied().unwrap())
-                })
-                .collect(),
-            final_states: nfa.final_states,
+    /// Chapter 2.4.4 and 2.6.2
+    pub fn convert_to_dfa(self, dfa_size_limit: usize, minimization: Minimization) -> Result<Dfa, Error> {
+        let nfa = match minimization {
+            Minimization::Brzozowski => reachable(subset_of_reverse(
+                reachable(subset_of_reverse(self, dfa_size_limit)?),
+                dfa_size_limit,
+            )?),
+            Minimization::Hopcroft => hopcroft(reachable(subset(self, dfa_size_limit)?)),
+        };
+        let mut transitions: BTreeMap<(State, TransitionType), State> = BTreeMap::new();
+        let mut previous: Option<(State, TransitionType, State)> = None;
+
+        for (&(state, transition), dest_states) in &nfa.transitions {
+            // At this point the NFA only have one state in it's dest states
+            let dest_state = dest_states.iter().next().copied().unwrap();
+
+            // `subset` splits the ranges in the smallest pieces possible, join the neighbouring pieces that go to
+            // the same state back together
+            if let (
+                Some((previous_state, TransitionType::Range(previous_start, previous_end), previous_dest_state)),
+                TransitionType::Range(start, end),
+            ) = (previous, transition)
+            {
+                if previous_state == state && previous_end == start && previous_dest_state == dest_state {
+                    transitions.remove(&(state, TransitionType::Range(previous_start, previous_end)));
+                    transitions.insert((state, TransitionType::Range(previous_start, end)), dest_state);
+                    previous = Some((state, TransitionType::Range(previous_start, end), dest_state));
+                    continue;
+                }
+            }
+
+            transitions.insert((state, transition), dest_state);
+            previous = Some((state, transition, dest_state));
         }
+
+        Ok(Dfa {
+            dense: DenseDfa::new(nfa.start_state(), &nfa.final_states, &transitions),
+        })
     }
 
     pub fn states(&self) -> BTreeSet<State> {
@@ -193,13 +406,21 @@ impl Automata {
         &self.final_states
     }
 
-    fn alphabet(&self) -> BTreeSet<TransitionType> {
-        self.transitions
-            .iter()
-            .filter_map(|(&(_, transition_type), _)| match transition_type {
-                TransitionType::Epsilon => None,
-                transition => Some(transition),
-            })
+    /// The letters of the alphabet are the disjoint ranges obtained by splitting the ranges of all transitions,
+    /// this way every letter is either fully matched by a transition or not matched at all. Because of that,
+    /// transitions that overlap, like "." and "a", are followed together when reading the letters they share.
+    pub fn alphabet(&self) -> Vec<TransitionType> {
+        let ranges = self
+            .transitions
+            .keys()
+            .filter_map(|&(_, transition_type)| match transition_type {
+                TransitionType::Range(start, end) => Some((start, end)),
+                TransitionType::Epsilon | TransitionType::Assertion(_) | TransitionType::Bytes(..) => None,
+            });
+
+        partition(ranges)
+            .into_iter()
+            .map(|(start, end)| TransitionType::Range(start, end))
             .collect()
     }
 
@@ -208,10 +429,7 @@ impl Automata {
     }
 
     pub fn add_transition(&mut self, state: State, symbol: TransitionType, dest: State) {
-        self.transitions
-            .entry((state, symbol))
-            .or_insert_with(BTreeSet::new)
-            .insert(dest);
+        self.transitions.entry((state, symbol)).or_default().insert(dest);
     }
 
     pub fn add_final_state(&mut self, state: State) {
@@ -223,12 +441,18 @@ impl Automata {
     }
 }
 
-pub fn build_automata_from_ast(tree: RegexAST, state: &mut State) -> Automata {
+/// Build an NFA from the tree, numbering its states from `state`. Fails if the NFA would have more than `limit`
+/// states.
+pub fn build_automata_from_ast(tree: RegexAST, state: &mut State, limit: usize) -> Result<Automata, Error> {
+    if *state > limit {
+        return Err(too_big("NFA", limit));
+    }
+
     let mut automata = Automata::new(*state);
     match tree {
         RegexAST::Binary(lhs, op, rhs) => {
-            let mut lhs = build_automata_from_ast(*lhs, state);
-            let rhs = build_automata_from_ast(*rhs, state);
+            let mut lhs = build_automata_from_ast(*lhs, state, limit)?;
+            let rhs = build_automata_from_ast(*rhs, state, limit)?;
 
             match op {
                 BinaryOp::Union => {
@@ -250,85 +474,131 @@ pub fn build_automata_from_ast(tree: RegexAST, state: &mut State) -> Automata {
             automata.merge_automata(lhs);
             automata.merge_automata(rhs);
         }
+        RegexAST::Unary(lhs, UnaryOp::Repetition { min, max }) => {
+            return build_automata_from_ast(expand_repetition(*lhs, min, max), state, limit)
+        }
         RegexAST::Unary(lhs, op) => {
-            let lhs = build_automata_from_ast(*lhs, state);
+            let lhs = build_automata_from_ast(*lhs, state, limit)?;
 
-            for final_state in lhs.final_states() {
-                automata.add_transition(*final_state, TransitionType::Epsilon, lhs.start_state);
+            if matches!(op, UnaryOp::ClosurePlus | UnaryOp::ClosureStar) {
+                for final_state in lhs.final_states() {
+                    automata.add_transition(*final_state, TransitionType::Epsilon, lhs.start_state);
+                }
             }
 
-            match op {
-                UnaryOp::ClosurePlus => {
-                    let new_start = *state;
-                    automata.add_transition(new_start, TransitionType::Epsilon, lhs.start_state);
-                    automata.start_state = new_start;
-                }
-                UnaryOp::ClosureStar => {
-                    let new_start = *state;
-                    automata.add_transition(new_start, TransitionType::Epsilon, lhs.start_state);
-                    automata.start_state = new_start;
-                    automata.add_final_state(new_start);
-                }
+            let new_start = *state;
+            automata.add_transition(new_start, TransitionType::Epsilon, lhs.start_state);
+            automata.start_state = new_start;
+            if matches!(op, UnaryOp::ClosureStar | UnaryOp::Optional) {
+                automata.add_final_state(new_start);
             }
 
             *state += 1;
 
             automata.merge_automata(lhs);
         }
-        RegexAST::Symbol(symbol) => return create_automata_for_transtition_type(TransitionType::Symbol(symbol), state),
-        RegexAST::CharacterClass(character_class_type) => return parse_character_class(character_class_type, state),
-        RegexAST::EmptyString => return create_automata_for_transtition_type(TransitionType::Epsilon, state),
-        RegexAST::AnyCharacter => return create_automata_for_transtition_type(TransitionType::AnyCharacter, state),
+        RegexAST::Symbol(symbol) => {
+            return Ok(create_automata_for_transtition_type(
+                TransitionType::symbol(symbol),
+                state,
+            ))
+        }
+        RegexAST::CharacterClass(character_class_type) => {
+            return Ok(parse_character_class(character_class_type, state))
+        }
+        RegexAST::EmptyString => return Ok(create_automata_for_transtition_type(TransitionType::Epsilon, state)),
+        RegexAST::AnyCharacter => {
+            return Ok(create_automata_for_transtition_type(
+                TransitionType::any_character(),
+                state,
+            ))
+        }
+        RegexAST::Bytes(bytes) => {
+            let final_state = *state + 1;
+            automata.add_final_state(final_state);
+            *state += 2;
+
+            for &(start, end) in bytes.intervals() {
+                automata.add_transition(automata.start_state, TransitionType::Bytes(start, end), final_state);
+            }
+        }
+        // The DFA only answers if there is a match, so the groups don't change the automaton
+        RegexAST::Group(lhs, ..) => return build_automata_from_ast(*lhs, state, limit),
+        RegexAST::Assertion(assertion) => {
+            return Ok(create_automata_for_transtition_type(
+                TransitionType::Assertion(assertion),
+                state,
+            ))
+        }
     }
 
-    automata
+    Ok(automata)
 }
 
-fn parse_character_class(char_class_type: CharacterClassType, state: &mut usize) -> Automata {
-    let mut automata = Automata::new(*state);
-    match char_class_type {
-        CharacterClassType::Single(symbol) => {
-            return create_automata_for_transtition_type(TransitionType::Symbol(symbol), state);
+/// Rewrite `a{m,n}` as `m` copies of `a` followed by `n - m` copies of `a?`, and `a{m,}` as `m` copies of `a`
+/// followed by `a*`.
+pub fn expand_repetition(tree: RegexAST, min: u32, max: Option<u32>) -> RegexAST {
+    let optional_copies = match max {
+        Some(max) => (max - min) as usize,
+        None => 0,
+    };
+
+    let mut copies: Vec<RegexAST> = std::iter::repeat_n(tree.clone(), min as usize)
+        .chain(
+            max.is_none()
+                .then(|| RegexAST::Unary(Box::new(tree.clone()), UnaryOp::ClosureStar)),
+        )
+        .chain(std::iter::repeat_n(
+            RegexAST::Unary(Box::new(tree), UnaryOp::Optional),
+            optional_copies,
+        ))
+        .collect();
+
+    // Concatenate the neighbouring copies in pairs until only one is left, the tree is balanced so building it
+    // only recurses as deep as the logarithm of the number of copies
+    while copies.len() > 1 {
+        let mut pairs = Vec::with_capacity(copies.len().div_ceil(2));
+        let mut copies_iter = copies.into_iter();
+        while let Some(lhs) = copies_iter.next() {
+            pairs.push(match copies_iter.next() {
+                Some(rhs) => RegexAST::Binary(Box::new(lhs), BinaryOp::Concatenation, Box::new(rhs)),
+                None => lhs,
+            });
         }
-        CharacterClassType::Binary(lhs, CharacterClassBinaryOp::Union, rhs) => {
-            let lhs = parse_character_class(*lhs, state);
-            let rhs = parse_character_class(*rhs, state);
-
-            automata.start_state = *state;
-            automata.add_transition(*state, TransitionType::Epsilon, lhs.start_state);
-            automata.add_transition(*state, TransitionType::Epsilon, rhs.start_state);
+        copies = pairs;
+    }
 
-            automata.merge_automata(lhs);
-            automata.merge_automata(rhs);
+    copies.pop().unwrap_or(RegexAST::EmptyString)
+}
 
-            *state += 1;
-        }
-        CharacterClassType::Binary(lhs, CharacterClassBinaryOp::Range, rhs) => {
-            let lhs = if let CharacterClassType::Single(lhs) = *lhs {
-                lhs
-            } else {
-                panic!("Wrong type for lhs in range")
-            };
-            let rhs = if let CharacterClassType::Single(rhs) = *rhs {
-                rhs
-            } else {
-                panic!("Wrong type for rhs in range")
-            };
+fn parse_character_class(char_class_type: CharacterClassType, state: &mut usize) -> Automata {
+    let mut automata = Automata::new(*state);
+    let final_state = *state + 1;
+    automata.add_final_state(final_state);
+    *state += 2;
 
-            automata.start_state = *state;
-            *state += 1;
-            let final_state = *state;
-            *state += 1;
-            automata.add_final_state(final_state);
-            for symbol in lhs..=rhs {
-                automata.add_transition(automata.start_state(), TransitionType::Symbol(symbol), final_state);
-            }
-        }
+    for &(start, end) in character_class_intervals(char_class_type).intervals() {
+        automata.add_transition(automata.start_state(), TransitionType::Range(start, end), final_state);
     }
 
     automata
 }
 
+pub fn character_class_intervals(char_class_type: CharacterClassType) -> IntervalSet {
+    match char_class_type {
+        CharacterClassType::Single(symbol) => IntervalSet::from_range(symbol, symbol),
+        CharacterClassType::Binary(lhs, CharacterClassBinaryOp::Union, rhs) => {
+            character_class_intervals(*lhs).union(&character_class_intervals(*rhs))
+        }
+        CharacterClassType::Binary(lhs, CharacterClassBinaryOp::Range, rhs) => match (*lhs, *rhs) {
+            (CharacterClassType::Single(lhs), CharacterClassType::Single(rhs)) => IntervalSet::from_range(lhs, rhs),
+            _ => panic!("Wrong type for range"),
+        },
+        CharacterClassType::Negated(char_class_type) => character_class_intervals(*char_class_type).complement(),
+        CharacterClassType::Set(set) => set,
+    }
+}
+
 fn create_automata_for_transtition_type(transition: TransitionType, state: &mut State) -> Automata {
     let mut automata = Automata::new(*state);
     automata.add_transition(*state, transition, *state + 1);
@@ -339,15 +609,149 @@ fn create_automata_for_transtition_type(transition: TransitionType, state: &mut
     automata
 }
 
+/// The set of the kinds of character that can follow a position, as a bit mask of `Look`s
+type LookSet = u8;
+
+const ALL_LOOKS: LookSet = (1 << Look::ALL.len()) - 1;
+
+fn look_bit(look: Look) -> LookSet {
+    1 << look as u8
+}
+
+/// Build an automaton without assertion transitions that accepts the same strings.
+///
+/// Every state of the new automaton is a state of the original one, together with the kind of the last character
+/// read (the look-behind) and the kinds of character that the next one can be (the look-ahead). Taking an
+/// assertion transition checks the assertion against the look-behind and restricts the look-ahead, then reading
+/// a character checks it against the look-ahead. Because of this every range is split by the kinds of the
+/// characters it contains, and a state only accepts if the text can end there.
+pub fn resolve_assertions(automata: Automata) -> Automata {
+    // Splitting the ranges by word characters creates a lot of transitions, so it is only done when some
+    // assertion needs it. Otherwise the word characters are treated as any other character, and the ASCII
+    // assertions treat the other word characters like that too.
+    let needs_words = |assertions: [Assertion; 2]| {
+        automata.transitions.keys().any(|&(_, transition)| {
+            matches!(transition, TransitionType::Assertion(assertion) if assertions.contains(&assertion))
+        })
+    };
+    let looks: Vec<(Look, IntervalSet)> = if needs_words([Assertion::WordBoundary, Assertion::NotWordBoundary]) {
+        Look::ALL.iter().map(|&look| (look, look.characters())).collect()
+    } else {
+        let mut looks = vec![
+            (Look::Boundary, IntervalSet::default()),
+            (Look::LineFeed, Look::LineFeed.characters()),
+        ];
+        if needs_words([Assertion::WordBoundaryAscii, Assertion::NotWordBoundaryAscii]) {
+            looks.push((Look::Word, Look::Word.characters()));
+        }
+        let other = looks
+            .iter()
+            .fold(IntervalSet::default(), |characters, (_, look_characters)| {
+                characters.union(look_characters)
+            })
+            .complement();
+        looks.push((Look::Other, other));
+
+        looks
+    };
+    let start = (automata.start_state(), Look::Boundary, ALL_LOOKS);
+    let mut new_automata = Automata::new(0);
+    let mut new_states: HashMap<(State, Look, LookSet), State> = HashMap::from([(start, 0)]);
+    let mut work_list = vec![start];
+
+    while let Some(key @ (state, behind, ahead)) = work_list.pop() {
+        let new_state = new_states[&key];
+
+        if automata.final_states().contains(&state) && ahead & look_bit(Look::Boundary) != 0 {
+            new_automata.add_final_state(new_state);
+        }
+
+        let transitions = automata
+            .transitions
+            .range((state, TransitionType::Epsilon)..(state + 1, TransitionType::Epsilon));
+        for (&(_, transition), dest_states) in transitions {
+            let dest_keys: Vec<(TransitionType, (State, Look, LookSet))> = match transition {
+                TransitionType::Epsilon => dest_states
+                    .iter()
+                    .map(|&dest_state| (TransitionType::Epsilon, (dest_state, behind, ahead)))
+                    .collect(),
+                TransitionType::Assertion(assertion) => {
+                    let allowed = Look::ALL
+                        .into_iter()
+                        .filter(|&look| assertion.is_satisfied(behind, look))
+                        .fold(0, |looks, look| looks | look_bit(look));
+
+                    match ahead & allowed {
+                        0 => vec![],
+                        ahead => dest_states
+                            .iter()
+                            .map(|&dest_state| (TransitionType::Epsilon, (dest_state, behind, ahead)))
+                            .collect(),
+                    }
+                }
+                TransitionType::Range(start, end) => looks
+                    .iter()
+                    .filter(|&&(look, _)| ahead & look_bit(look) != 0)
+                    .flat_map(|&(look, ref characters)| {
+                        characters
+                            .intersection(&IntervalSet::new([(start, end)]))
+                            .intervals()
+                            .iter()
+                            .flat_map(|&(start, end)| {
+                                dest_states.iter().map(move |&dest_state| {
+                                    (TransitionType::Range(start, end), (dest_state, look, ALL_LOOKS))
+                                })
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .collect(),
+                // A byte that isn't ASCII isn't a character by itself, so it is like any other character that
+                // isn't a line feed or a word character
+                TransitionType::Bytes(start, end) => looks
+                    .iter()
+                    .filter(|&&(look, _)| ahead & look_bit(look) != 0)
+                    .flat_map(|&(look, ref characters)| {
+                        let non_ascii = if look == Look::Other { 0x80..0x100 } else { 0..0 };
+                        IntervalSet::new([(0, 0x80)])
+                            .intersection(characters)
+                            .union(&IntervalSet::new([(non_ascii.start, non_ascii.end)]))
+                            .intersection(&IntervalSet::new([(start, end)]))
+                            .intervals()
+                            .iter()
+                            .flat_map(|&(start, end)| {
+                                dest_states.iter().map(move |&dest_state| {
+                                    (TransitionType::Bytes(start, end), (dest_state, look, ALL_LOOKS))
+                                })
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .collect(),
+            };
+
+            for (transition, dest_key) in dest_keys {
+                let next_state = new_states.len();
+                let dest_state = *new_states.entry(dest_key).or_insert_with(|| {
+                    work_list.push(dest_key);
+                    next_state
+                });
+                new_automata.add_transition(new_state, transition, dest_state);
+            }
+        }
+    }
+
+    new_automata
+}
+
 pub fn reachable(mut automata: Automata) -> Automata {
     let alphabet = automata.alphabet();
     let mut reachable_states = BTreeSet::from([automata.start_state()]);
     let mut new_states = BTreeSet::from([automata.start_state()]);
 
     while !new_states.is_empty() {
-        new_states = &alphabet
-            .iter()
-            .flat_map(|symbol| automata.delta(&new_states, *symbol))
+        new_states = &automata
+            .delta(&new_states, &alphabet)
+            .into_iter()
+            .flatten()
             .collect::<BTreeSet<State>>()
             - &reachable_states;
 
@@ -362,6 +766,73 @@ pub fn reachable(mut automata: Automata) -> Automata {
     automata
 }
 
+/// Build an automaton that reads the UTF-8 encoding of the characters one byte at a time, instead of a character
+/// at a time. Every range of characters is replaced by the sequences of byte ranges of `utf8_sequences`, and each
+/// sequence by a path of new states. The automaton must not have assertion transitions. Fails if the new automaton
+/// would have more than `limit` states.
+///
+/// A class like `\w` has hundreds of sequences that start with the same byte ranges, so the paths leaving a state
+/// share the states of their common prefixes.
+pub fn utf8_automata(automata: Automata, limit: usize) -> Result<Automata, Error> {
+    let mut new_automata = Automata::new(automata.start_state);
+    new_automata.final_states = automata.final_states.clone();
+    let mut next_state = automata
+        .states()
+        .iter()
+        .max()
+        .copied()
+        .unwrap_or_default()
+        .max(automata.start_state)
+        + 1;
+    let mut prefix_states: HashMap<(State, Vec<(u8, u8)>), State> = HashMap::new();
+
+    for ((state, transition), dest_states) in automata.transitions {
+        match transition {
+            TransitionType::Range(start, end) => {
+                for sequence in utf8_sequences(start, end) {
+                    let (&(last_start, last_end), path) = sequence.split_last().unwrap();
+
+                    let mut path_state = state;
+                    for (length, &(start, end)) in path.iter().enumerate() {
+                        let prefix_state =
+                            *prefix_states
+                                .entry((state, path[..=length].to_vec()))
+                                .or_insert_with(|| {
+                                    next_state += 1;
+                                    next_state - 1
+                                });
+                        if next_state > limit {
+                            return Err(too_big("NFA", limit));
+                        }
+                        new_automata.add_transition(path_state, byte_range(start, end), prefix_state);
+                        path_state = prefix_state;
+                    }
+                    for &dest_state in &dest_states {
+                        new_automata.add_transition(path_state, byte_range(last_start, last_end), dest_state);
+                    }
+                }
+            }
+            TransitionType::Bytes(start, end) => {
+                for dest_state in dest_states {
+                    new_automata.add_transition(state, TransitionType::Range(start, end), dest_state);
+                }
+            }
+            TransitionType::Epsilon | TransitionType::Assertion(_) => {
+                for dest_state in dest_states {
+                    new_automata.add_transition(state, transition, dest_state);
+                }
+            }
+        }
+    }
+
+    Ok(new_automata)
+}
+
+/// A transition that matches the bytes between `start` and `end` inclusive
+fn byte_range(start: u8, end: u8) -> TransitionType {
+    TransitionType::Range(u32::from(start), u32::from(end) + 1)
+}
+
 pub fn reverse(automata: Automata) -> Automata {
     let mut new_automata = Automata::new(automata.states().iter().max().copied().unwrap_or_default() + 1);
     new_automata.add_final_state(automata.start_state);
@@ -379,54 +850,169 @@ pub fn reverse(automata: Automata) -> Automata {
     new_automata
 }
 
-pub fn subset(automata: Automata) -> Automata {
+/// Build a DFA with the subset construction. Fails if the DFA would have more than `limit` states.
+pub fn subset(automata: Automata, limit: usize) -> Result<Automata, Error> {
+    let start_states = BTreeSet::from([automata.start_state()]);
+    subset_from(automata, start_states, limit)
+}
+
+/// The subset construction of the reverse of the automata, the step of Brzozowski's algorithm. The reverse starts in
+/// all of the final states of the automata at once, instead of in the start state `reverse` adds: that state doesn't
+/// read anything, but the set with it would be a different DFA state from the set of the final states without it,
+/// and the DFA wouldn't be minimal.
+fn subset_of_reverse(automata: Automata, limit: usize) -> Result<Automata, Error> {
+    let start_states = automata.final_states.clone();
+    subset_from(reverse(automata), start_states, limit)
+}
+
+/// The subset construction starting in the set of states
+fn subset_from(automata: Automata, start_states: BTreeSet<State>, limit: usize) -> Result<Automata, Error> {
     let alphabet = automata.alphabet();
-    let dest_states = automata.eclosure(BTreeSet::from([automata.start_state()]));
+    let dest_states = automata.eclosure(start_states);
 
-    let mut work_list = Vec::new();
+    let mut new_states = SubsetInterner::default();
+    let (start_state, _) = new_states.intern(&dest_states);
+    let mut new_automata = Automata::new(start_state);
+    let mut work_list = vec![(start_state, dest_states)];
 
-    let mut curr_state = 0;
-    let mut dest_state = 0;
-    let mut new_automata = Automata::new(curr_state);
-    let mut new_states: HashMap<u64, State> = HashMap::new();
+    while let Some((curr_state, states)) = work_list.pop() {
+        if !(&automata.final_states & &states).is_empty() {
+            new_automata.add_final_state(curr_state);
+        }
 
-    new_states.insert(calculate_hash(&dest_states), curr_state);
-    work_list.push(dest_states);
+        for (symbol, dest_states) in alphabet.iter().zip(automata.delta(&states, &alphabet)) {
+            let subset = automata.eclosure(dest_states);
+            let (dest_state, is_new) = new_states.intern(&subset);
 
-    while !work_list.is_empty() {
-        let states = work_list.pop().unwrap();
+            new_automata.add_transition(curr_state, *symbol, dest_state);
 
-        if let Some(state) = new_states.get(&calculate_hash(&states)) {
-            curr_state = *state;
+            if is_new {
+                work_list.push((dest_state, subset));
+            }
         }
 
-        if !(&automata.final_states & &states).is_empty() {
-            new_automata.add_final_state(curr_state);
+        if new_states.len() > limit {
+            return Err(too_big("DFA", limit));
         }
+    }
 
-        for symbol in &alphabet {
-            let subset = automata.eclosure(automata.delta(&states, *symbol));
+    Ok(new_automata)
+}
 
-            let subset_hash = calculate_hash(&subset);
-            dest_state = new_states.get(&subset_hash).copied().unwrap_or_else(|| {
-                // TODO: find a better way to create state labels that doesnt exist
-                loop {
-                    dest_state += 1;
-                    if !new_states.values().any(|&state| state == dest_state) {
-                        break;
-                    }
+/// The labels of the sets of NFA states found by the subset construction, given in the order the sets are found.
+/// The sets are compared by their states, so two different sets never get the same label.
+#[derive(Debug, Default)]
+struct SubsetInterner {
+    labels: HashMap<Box<[State]>, State>,
+}
+
+impl SubsetInterner {
+    /// The label of the set, and if the set wasn't found before
+    fn intern(&mut self, states: &BTreeSet<State>) -> (State, bool) {
+        let next_label = self.labels.len();
+
+        match self.labels.entry(states.iter().copied().collect()) {
+            Entry::Occupied(entry) => (*entry.get(), false),
+            Entry::Vacant(entry) => (*entry.insert(next_label), true),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.labels.len()
+    }
+}
+
+/// Minimize a DFA built by `subset` with Hopcroft's partition refinement. The states start split in final and
+/// non-final ones, then every block is split by the states that go to a splitter block by some letter, until no
+/// block can be split. The states left in the same block are equivalent and become a single state, labeled by the
+/// index of the block.
+///
+/// The DFA must be complete, as `subset` builds it: every state has a transition for every letter of the alphabet.
+pub fn hopcroft(automata: Automata) -> Automata {
+    let alphabet = automata.alphabet();
+    let mut states = automata.states();
+    states.insert(automata.start_state);
+    states.extend(&automata.final_states);
+
+    // The states that go to a state by every letter
+    let mut sources: HashMap<(State, TransitionType), Vec<State>> = HashMap::new();
+    for (&(state, transition), dest_states) in &automata.transitions {
+        for &dest_state in dest_states {
+            sources.entry((dest_state, transition)).or_default().push(state);
+        }
+    }
+
+    let (final_states, other_states): (BTreeSet<State>, BTreeSet<State>) =
+        states.iter().partition(|state| automata.final_states.contains(state));
+    let mut blocks: Vec<BTreeSet<State>> = [final_states, other_states]
+        .into_iter()
+        .filter(|block| !block.is_empty())
+        .collect();
+    let mut block_of: HashMap<State, usize> = HashMap::new();
+    for (index, block) in blocks.iter().enumerate() {
+        block_of.extend(block.iter().map(|&state| (state, index)));
+    }
+
+    // Only one of the halves of a split block has to be a splitter, as splitting by the other one gives the same
+    // blocks. Picking the smallest one, a state is in a splitter at most log n times, which gives the O(n log n)
+    // bound together with splitting in the time of the split states.
+    let mut work_list: Vec<usize> = (0..blocks.len()).collect();
+    let mut in_work_list = vec![true; blocks.len()];
+
+    while let Some(splitter) = work_list.pop() {
+        in_work_list[splitter] = false;
+        let splitter_states = blocks[splitter].clone();
+
+        for letter in &alphabet {
+            // The states that go to the splitter by the letter, grouped by their block
+            let mut split_states: BTreeMap<usize, BTreeSet<State>> = BTreeMap::new();
+            for state in &splitter_states {
+                for &source in sources.get(&(*state, *letter)).into_iter().flatten() {
+                    split_states.entry(block_of[&source]).or_default().insert(source);
                 }
-                dest_state
-            });
+            }
 
-            new_automata.add_transition(curr_state, *symbol, dest_state);
+            for (block, split) in split_states {
+                if split.len() == blocks[block].len() {
+                    continue;
+                }
 
-            if let Entry::Vacant(new_states) = new_states.entry(subset_hash) {
-                new_states.insert(dest_state);
-                work_list.push(subset);
+                // Only the split states are moved, so splitting costs the transitions that were read to find them
+                // instead of the size of the block
+                let new_block = blocks.len();
+                for &state in &split {
+                    blocks[block].remove(&state);
+                    block_of.insert(state, new_block);
+                }
+                blocks.push(split);
+
+                if in_work_list[block] || blocks[new_block].len() <= blocks[block].len() {
+                    work_list.push(new_block);
+                    in_work_list.push(true);
+                } else {
+                    work_list.push(block);
+                    in_work_list[block] = true;
+                    in_work_list.push(false);
+                }
             }
         }
     }
 
+    let mut new_automata = Automata::new(block_of[&automata.start_state]);
+    for &final_state in &automata.final_states {
+        new_automata.add_final_state(block_of[&final_state]);
+    }
+    for (&(state, transition), dest_states) in &automata.transitions {
+        for dest_state in dest_states {
+            new_automata.add_transition(block_of[&state], transition, block_of[dest_state]);
+        }
+    }
+
     new_automata
 }
+
+fn too_big(automaton: &str, limit: usize) -> Error {
+    Error::TooBig(format!(
+        "Regex too big: the {automaton} exceeds the size limit of {limit} states!"
+    ))
+}
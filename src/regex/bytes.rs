@@ -0,0 +1,142 @@
+use std::ops::Range;
+
+use super::{
+    automata::{utf8_automata, Automata, Matcher},
+    lazy::LazyDfa,
+    nfa::Program,
+    parser::{concatenate, parse_regex_with_config, Assertion, RegexAST},
+    pikevm::{CachePool, PikeVM},
+    Engine, Error, RegexBuilder,
+};
+
+/// A regex that matches bytes instead of a `&str`, for haystacks that can have invalid UTF-8 like binary files or
+/// Latin-1 text. The characters of the pattern match their UTF-8 encoding, so `\p{Greek}` matches the bytes of a
+/// Greek letter, and the bytes that aren't valid UTF-8 are only matched by `.` when
+/// [`RegexBuilder::dot_matches_any_byte`] is enabled.
+#[derive(Debug)]
+pub struct Regex {
+    automaton: Matcher,
+    program: Program,
+    /// The memory of the searches of `program`, reused by all of them
+    caches: CachePool,
+    /// The program of the regex anchored at both ends, it replaces the automaton in [`Regex::is_match`] when the
+    /// regex has assertions and a `.` that matches any byte. The automaton reads those bytes one at a time, so it
+    /// can't know if they are part of a valid character, and an assertion between them mustn't match.
+    full_match_program: Option<Program>,
+    /// The memory of the searches of `full_match_program`
+    full_match_caches: CachePool,
+}
+
+impl Regex {
+    pub fn new(re: &str) -> Result<Self, Error> {
+        RegexBuilder::new(re).build_bytes()
+    }
+
+    /// Check if the whole `text` is matched by the regex.
+    pub fn is_match(&self, text: &[u8]) -> bool {
+        match &self.full_match_program {
+            Some(program) => PikeVM::new(program)
+                .search(&mut self.full_match_caches.get(program), text, 0)
+                .is_some(),
+            None => self.automaton.validate_bytes(text),
+        }
+    }
+
+    /// The engine used by [`Regex::is_match`], like [`super::Regex::engine`]. It is always the NFA when the regex
+    /// has assertions and `.` matches any byte.
+    pub fn engine(&self) -> Engine {
+        match self.full_match_program {
+            Some(_) => Engine::Nfa,
+            None => self.automaton.engine(),
+        }
+    }
+
+    /// Find the leftmost-first match in `haystack`, like [`super::Regex::find`]. The match can start and end in
+    /// the middle of a character when `.` matches any byte.
+    pub fn find<'h>(&self, haystack: &'h [u8]) -> Option<Match<'h>> {
+        let slots = PikeVM::new(&self.program).search(&mut self.caches.get(&self.program), haystack, 0)?;
+
+        // The group 0 is always set in the slots of a match
+        Some(Match {
+            haystack,
+            start: slots[0].unwrap(),
+            end: slots[1].unwrap(),
+        })
+    }
+}
+
+impl RegexBuilder {
+    /// Build a [`Regex`] that matches bytes, with the same options as [`RegexBuilder::build`].
+    pub fn build_bytes(&self) -> Result<Regex, Error> {
+        let tree = parse_regex_with_config(&self.pattern, &self.config)?;
+        let nfa = utf8_automata(
+            Automata::nfa_from_regex_expr(tree.clone(), self.limits.nfa_states)?,
+            self.limits.nfa_states,
+        )?;
+        let automaton = if self.lazy_dfa {
+            Matcher::LazyDfa(LazyDfa::new(nfa, self.limits.dfa_states))
+        } else {
+            nfa.into_matcher(self.limits.dfa_states, self.minimization)?
+        };
+
+        let full_match_program = (has_leaf(&tree, &|leaf| matches!(leaf, RegexAST::Assertion(_)))
+            && has_leaf(
+                &tree,
+                &|leaf| matches!(leaf, RegexAST::Bytes(bytes) if bytes.intervals().iter().any(|&(_, end)| end > 0x80)),
+            ))
+        .then(|| {
+            let anchored = concatenate(
+                concatenate(RegexAST::Assertion(Assertion::StartText), tree.clone()),
+                RegexAST::Assertion(Assertion::EndText),
+            );
+            Program::new_bytes(anchored)
+        });
+
+        Ok(Regex {
+            automaton,
+            program: Program::new_bytes(tree),
+            caches: CachePool::default(),
+            full_match_program,
+            full_match_caches: CachePool::default(),
+        })
+    }
+}
+
+/// Check if a leaf of the tree satisfies the predicate
+fn has_leaf(tree: &RegexAST, predicate: &impl Fn(&RegexAST) -> bool) -> bool {
+    match tree {
+        RegexAST::Binary(lhs, _, rhs) => has_leaf(lhs, predicate) || has_leaf(rhs, predicate),
+        RegexAST::Unary(lhs, _) | RegexAST::Group(lhs, _, _) => has_leaf(lhs, predicate),
+        leaf => predicate(leaf),
+    }
+}
+
+/// A match of a [`Regex`] in a haystack of bytes, the positions are byte offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match<'h> {
+    haystack: &'h [u8],
+    start: usize,
+    end: usize,
+}
+
+impl<'h> Match<'h> {
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn as_bytes(&self) -> &'h [u8] {
+        &self.haystack[self.range()]
+    }
+}
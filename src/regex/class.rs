@@ -0,0 +1,144 @@
+use super::interval::IntervalSet;
+use super::unicode_tables::{
+    case_folding::CASE_FOLDING_SIMPLE, general_category, perl_decimal::DECIMAL_NUMBER, perl_space::WHITE_SPACE,
+    perl_word::PERL_WORD, script,
+};
+
+/// The Perl shorthand classes, their negations `\D`, `\S` and `\W` are the complement of these
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PerlClass {
+    /// \d => a decimal digit
+    Digit,
+    /// \s => a whitespace
+    Space,
+    /// \w => a word character, letters, digits and "_"
+    Word,
+}
+
+impl PerlClass {
+    /// The characters of the class, with `unicode` they follow UTS #18 Annex C, otherwise only ASCII characters
+    /// are included
+    pub fn intervals(self, unicode: bool) -> IntervalSet {
+        match (self, unicode) {
+            (PerlClass::Digit, true) => IntervalSet::from_table(DECIMAL_NUMBER),
+            (PerlClass::Space, true) => IntervalSet::from_table(WHITE_SPACE),
+            (PerlClass::Word, true) => IntervalSet::from_table(PERL_WORD),
+            (PerlClass::Digit, false) => IntervalSet::from_table(&[('0', '9')]),
+            (PerlClass::Space, false) => IntervalSet::from_table(&[('\t', '\r'), (' ', ' ')]),
+            (PerlClass::Word, false) => IntervalSet::from_table(&[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')]),
+        }
+    }
+}
+
+/// The POSIX classes used inside brackets like `[[:alpha:]]`, they only contain ASCII characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PosixClass {
+    Alnum,
+    Alpha,
+    Blank,
+    Cntrl,
+    Digit,
+    Graph,
+    Lower,
+    Print,
+    Punct,
+    Space,
+    Upper,
+    Xdigit,
+}
+
+impl PosixClass {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "alnum" => PosixClass::Alnum,
+            "alpha" => PosixClass::Alpha,
+            "blank" => PosixClass::Blank,
+            "cntrl" => PosixClass::Cntrl,
+            "digit" => PosixClass::Digit,
+            "graph" => PosixClass::Graph,
+            "lower" => PosixClass::Lower,
+            "print" => PosixClass::Print,
+            "punct" => PosixClass::Punct,
+            "space" => PosixClass::Space,
+            "upper" => PosixClass::Upper,
+            "xdigit" => PosixClass::Xdigit,
+            _ => return None,
+        })
+    }
+
+    pub fn intervals(self) -> IntervalSet {
+        IntervalSet::from_table(match self {
+            PosixClass::Alnum => &[('0', '9'), ('A', 'Z'), ('a', 'z')],
+            PosixClass::Alpha => &[('A', 'Z'), ('a', 'z')],
+            PosixClass::Blank => &[('\t', '\t'), (' ', ' ')],
+            PosixClass::Cntrl => &[('\0', '\x1F'), ('\x7F', '\x7F')],
+            PosixClass::Digit => &[('0', '9')],
+            PosixClass::Graph => &[('!', '~')],
+            PosixClass::Lower => &[('a', 'z')],
+            PosixClass::Print => &[(' ', '~')],
+            PosixClass::Punct => &[('!', '/'), (':', '@'), ('[', '`'), ('{', '~')],
+            PosixClass::Space => &[('\t', '\r'), (' ', ' ')],
+            PosixClass::Upper => &[('A', 'Z')],
+            PosixClass::Xdigit => &[('0', '9'), ('A', 'F'), ('a', 'f')],
+        })
+    }
+}
+
+/// The characters of a Unicode general category or script. The name can be a short or a long name like `L`,
+/// `Letter`, `Grek` or `Greek`, optionally prefixed by its property like `gc=L` or `Script=Greek`. The names are
+/// compared ignoring case, spaces, `_` and `-`.
+pub fn unicode_property(name: &str) -> Option<IntervalSet> {
+    let general_category = (general_category::BY_NAME, general_category::ALIASES);
+    let script = (script::BY_NAME, script::ALIASES);
+
+    match name.split_once(['=', ':']) {
+        Some((property, value)) => match normalize_property_name(property).as_str() {
+            "gc" | "generalcategory" => property_value(general_category, value),
+            "sc" | "script" => property_value(script, value),
+            _ => None,
+        },
+        None => property_value(general_category, name).or_else(|| property_value(script, name)),
+    }
+}
+
+type PropertyTables = (
+    &'static [(&'static str, &'static [(char, char)])],
+    &'static [(&'static str, &'static str)],
+);
+
+fn property_value((by_name, aliases): PropertyTables, value: &str) -> Option<IntervalSet> {
+    let value = normalize_property_name(value);
+    let alias = aliases
+        .binary_search_by_key(&value.as_str(), |&(alias, _)| alias)
+        .ok()?;
+    let name = aliases[alias].1;
+    let table = by_name.binary_search_by_key(&name, |&(name, _)| name).ok()?;
+
+    Some(IntervalSet::from_table(by_name[table].1))
+}
+
+fn normalize_property_name(name: &str) -> String {
+    name.chars()
+        .filter(|symbol| !matches!(symbol, ' ' | '_' | '-'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Add to the set every character that has the same Unicode simple case folding as one of its characters, e.g.
+/// `k` adds `K` and the Kelvin sign `\u{212A}`.
+pub fn case_fold(set: &IntervalSet) -> IntervalSet {
+    let mut equivalents = Vec::new();
+
+    for &(start, end) in set.intervals() {
+        // Only the characters of the folding table can have equivalents, so walk the entries inside the interval
+        let first = CASE_FOLDING_SIMPLE.partition_point(|&(symbol, _)| (symbol as u32) < start);
+        for &(_, others) in CASE_FOLDING_SIMPLE[first..]
+            .iter()
+            .take_while(|&&(symbol, _)| (symbol as u32) < end)
+        {
+            equivalents.extend(others.iter().map(|&other| (other as u32, other as u32 + 1)));
+        }
+    }
+
+    set.union(&IntervalSet::new(equivalents))
+}
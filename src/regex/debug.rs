@@ -6,13 +6,15 @@ use graphviz_rust::{
 };
 
 use super::automata::{State, TransitionType};
+use super::interval::CODE_POINT_END;
+use super::parser::Assertion;
 
 type Node<'a> = (State, &'a str);
 type Edge<'a> = (Node<'a>, String, Node<'a>);
 
 pub trait AutomataDebug {
     fn start_state(&self) -> State;
-    fn final_states(&self) -> &BTreeSet<State>;
+    fn final_states(&self) -> BTreeSet<State>;
     fn states(&self) -> BTreeSet<State>;
     fn transitions(&self) -> BTreeMap<(usize, TransitionType), BTreeSet<State>>;
 }
@@ -45,14 +47,26 @@ impl AutomataPrinter {
 
         let mut duplicates: BTreeMap<(usize, usize), Vec<String>> = BTreeMap::new();
         for (state, t, dest_state) in edges {
-            duplicates
-                .entry((state, dest_state))
-                .or_insert_with(Vec::new)
-                .push(match t {
-                    TransitionType::AnyCharacter => String::from("<I>ANY CHAR</I>"),
-                    TransitionType::Epsilon => String::from("&epsilon;"),
-                    TransitionType::Symbol(symbol) => symbol.to_string(),
-                });
+            duplicates.entry((state, dest_state)).or_default().push(match t {
+                TransitionType::Epsilon => String::from("&epsilon;"),
+                TransitionType::Range(0, CODE_POINT_END) => String::from("<I>ANY CHAR</I>"),
+                TransitionType::Range(start, end) if start + 1 == end => format_code_point(start),
+                TransitionType::Range(start, end) => {
+                    format!("{}-{}", format_code_point(start), format_code_point(end - 1))
+                }
+                TransitionType::Bytes(start, end) if start + 1 == end => format!("byte {start:#04X}"),
+                TransitionType::Bytes(start, end) => format!("bytes {start:#04X}-{:#04X}", end - 1),
+                TransitionType::Assertion(assertion) => String::from(match assertion {
+                    Assertion::StartText => "\\A",
+                    Assertion::EndText => "\\z",
+                    Assertion::StartLine => "^",
+                    Assertion::EndLine => "$",
+                    Assertion::WordBoundary => "\\b",
+                    Assertion::NotWordBoundary => "\\B",
+                    Assertion::WordBoundaryAscii => "(?-u:\\b)",
+                    Assertion::NotWordBoundaryAscii => "(?-u:\\B)",
+                }),
+            });
         }
 
         let edges: Vec<(usize, String, usize)> = duplicates
@@ -64,7 +78,7 @@ impl AutomataPrinter {
             nodes,
             edges,
             start_state: nfa.start_state(),
-            final_states: nfa.final_states().clone(),
+            final_states: nfa.final_states(),
         }
     }
 
@@ -86,6 +100,10 @@ impl AutomataPrinter {
     }
 }
 
+fn format_code_point(code_point: u32) -> String {
+    char::from_u32(code_point).map_or_else(|| format!("\\u{{{code_point:X}}}"), |symbol| symbol.to_string())
+}
+
 impl<'a> dot::Labeller<'a, Node<'a>, Edge<'a>> for AutomataPrinter {
     fn graph_id(&'a self) -> dot::Id<'a> {
         dot::Id::new("AUTOMATON").unwrap()
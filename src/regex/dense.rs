@@ -0,0 +1,232 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use super::automata::{State, TransitionType};
+
+/// A state of the dense table, premultiplied by the number of classes so it is the start of the row of the state
+pub type StateId = usize;
+
+/// The state without transitions, once it is reached the text can't be matched. It is always the first row.
+const DEAD: StateId = 0;
+
+/// The runtime layout of a [`Dfa`](super::automata::Dfa): the symbols that have the same transitions in every
+/// state are an equivalence class, and the transitions are a table with a row per state and a column per class.
+/// Reading a symbol is finding its class and indexing the table, instead of searching the ranges of the state.
+#[derive(Debug, Clone)]
+pub struct DenseDfa {
+    /// The class of every symbol below 256, so ASCII characters and bytes are found without a search
+    byte_classes: Box<[usize; 256]>,
+    /// The starts of the disjoint ranges of symbols that the transitions don't split, the first one is always 0
+    boundaries: Vec<u32>,
+    /// The class of every range in `boundaries`, ranges that aren't neighbours can be in the same class
+    range_classes: Vec<usize>,
+    /// The number of classes, and the length of every row
+    stride: usize,
+    /// The destination of every state by every class, one row per state
+    transitions: Vec<StateId>,
+    start_state: StateId,
+    /// If the state of every row is final
+    final_states: Vec<bool>,
+}
+
+impl DenseDfa {
+    pub fn new(
+        start_state: State,
+        final_states: &BTreeSet<State>,
+        transitions: &BTreeMap<(State, TransitionType), State>,
+    ) -> Self {
+        let mut state_ranges: BTreeMap<State, Vec<(u32, u32, State)>> = BTreeMap::from([(start_state, Vec::new())]);
+        for (&(state, transition), &dest_state) in transitions {
+            if let TransitionType::Range(start, end) = transition {
+                state_ranges.entry(state).or_default().push((start, end, dest_state));
+            }
+            state_ranges.entry(dest_state).or_default();
+        }
+
+        let mut boundaries: Vec<u32> = state_ranges
+            .values()
+            .flatten()
+            .flat_map(|&(start, end, _)| [start, end])
+            .chain([0])
+            .collect();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+        let range_index = |symbol: u32| boundaries.partition_point(|&boundary| boundary <= symbol) - 1;
+
+        // Start with a single class and split it by the destinations of every state, the ranges that are left in
+        // the same class go to the same state from every state
+        let mut range_classes = vec![0; boundaries.len()];
+        for ranges in state_ranges.values() {
+            let mut dest_states = vec![None; boundaries.len()];
+            for &(start, end, dest_state) in ranges {
+                dest_states[range_index(start)..range_index(end)].fill(Some(dest_state));
+            }
+
+            let mut split_classes: HashMap<(usize, Option<State>), usize> = HashMap::new();
+            for (class, dest_state) in range_classes.iter_mut().zip(dest_states) {
+                let next_class = split_classes.len();
+                *class = *split_classes.entry((*class, dest_state)).or_insert(next_class);
+            }
+        }
+        let stride = range_classes.iter().max().map_or(1, |&class| class + 1);
+
+        // A state that isn't final and only goes to itself is a dead state, it shares the row of `DEAD`
+        let is_dead = |state: &State, ranges: &Vec<(u32, u32, State)>| {
+            !final_states.contains(state) && ranges.iter().all(|&(_, _, dest_state)| dest_state == *state)
+        };
+        let mut ids: HashMap<State, StateId> = HashMap::new();
+        let mut rows = 1;
+        for (state, ranges) in &state_ranges {
+            if is_dead(state, ranges) {
+                ids.insert(*state, DEAD);
+            } else {
+                ids.insert(*state, rows * stride);
+                rows += 1;
+            }
+        }
+
+        let mut dense_transitions = vec![DEAD; rows * stride];
+        let mut dense_final_states = vec![false; rows];
+        for (state, ranges) in &state_ranges {
+            let id = ids[state];
+            if id == DEAD {
+                continue;
+            }
+
+            dense_final_states[id / stride] = final_states.contains(state);
+            for &(start, end, dest_state) in ranges {
+                for &class in &range_classes[range_index(start)..range_index(end)] {
+                    dense_transitions[id + class] = ids[&dest_state];
+                }
+            }
+        }
+
+        let byte_classes = Box::new(std::array::from_fn(|byte| range_classes[range_index(byte as u32)]));
+
+        Self {
+            byte_classes,
+            boundaries,
+            range_classes,
+            stride,
+            transitions: dense_transitions,
+            start_state: ids[&start_state],
+            final_states: dense_final_states,
+        }
+    }
+
+    /// The number of equivalence classes of the symbols
+    pub fn classes(&self) -> usize {
+        self.stride
+    }
+
+    /// The number of rows of the table, the dead state included
+    pub fn rows(&self) -> usize {
+        self.final_states.len()
+    }
+
+    /// The row of the start state
+    pub fn start_row(&self) -> usize {
+        self.start_state / self.stride
+    }
+
+    /// The rows of the final states
+    pub fn final_rows(&self) -> BTreeSet<usize> {
+        (0..self.rows()).filter(|&row| self.final_states[row]).collect()
+    }
+
+    /// The transitions of every row by the ranges of symbols, the neighbouring ranges that go to the same row are
+    /// joined and the ones that go to the dead state are left out
+    pub fn range_transitions(&self) -> BTreeMap<(usize, TransitionType), usize> {
+        let mut transitions = BTreeMap::new();
+
+        for row in 0..self.rows() {
+            let mut previous: Option<(u32, u32, usize)> = None;
+            // The last range is after the end of every transition, so it always goes to the dead state
+            for (range, &class) in self.boundaries.windows(2).zip(&self.range_classes) {
+                let dest_state = self.transitions[row * self.stride + class];
+                let (start, end) = match previous {
+                    Some((previous_start, previous_end, previous_dest_state))
+                        if previous_end == range[0] && previous_dest_state == dest_state =>
+                    {
+                        transitions.remove(&(row, TransitionType::Range(previous_start, previous_end)));
+                        (previous_start, range[1])
+                    }
+                    _ => (range[0], range[1]),
+                };
+
+                if dest_state != DEAD {
+                    transitions.insert((row, TransitionType::Range(start, end)), dest_state / self.stride);
+                }
+                previous = Some((start, end, dest_state));
+            }
+        }
+
+        transitions
+    }
+
+    /// Check if the whole sequence of code points, or of bytes for the byte automata, is accepted
+    pub fn validate(&self, symbols: impl Iterator<Item = u32>) -> bool {
+        let mut state = self.start_state;
+
+        for symbol in symbols {
+            state = self.transitions[state + self.class(symbol)];
+            if state == DEAD {
+                return false;
+            }
+        }
+
+        self.final_states[state / self.stride]
+    }
+
+    fn class(&self, symbol: u32) -> usize {
+        match self.byte_classes.get(symbol as usize) {
+            Some(&class) => class,
+            None => self.range_classes[self.boundaries.partition_point(|&boundary| boundary <= symbol) - 1],
+        }
+    }
+}
+
+/// The transitions of a [`DenseDfa`] as ranges in a tree, the layout the DFA had before the table: reading a symbol
+/// searches the ranges of the state. It is only kept as the baseline of the `dense_table` benchmark.
+#[derive(Debug, Clone)]
+pub struct SparseDfa {
+    start_state: State,
+    final_states: BTreeSet<State>,
+    transitions: BTreeMap<(State, TransitionType), State>,
+}
+
+impl SparseDfa {
+    pub fn new(dense: &DenseDfa) -> Self {
+        Self {
+            start_state: dense.start_row(),
+            final_states: dense.final_rows(),
+            transitions: dense.range_transitions(),
+        }
+    }
+
+    fn next_state(&self, state: State, code_point: u32) -> Option<State> {
+        // The ranges leaving a state are disjoint, so the only one that can contain the symbol is the last one
+        // starting before it
+        self.transitions
+            .range((state, TransitionType::Epsilon)..=(state, TransitionType::Range(code_point, u32::MAX)))
+            .next_back()
+            .and_then(|(&(_, transition), &dest_state)| match transition {
+                TransitionType::Range(_, end) if code_point < end => Some(dest_state),
+                _ => None,
+            })
+    }
+
+    /// Like [`DenseDfa::validate`]
+    pub fn validate(&self, symbols: impl Iterator<Item = u32>) -> bool {
+        let mut state = Some(self.start_state);
+
+        for symbol in symbols {
+            if let Some(curr_state) = state {
+                state = self.next_state(curr_state, symbol);
+            } else {
+                break;
+            }
+        }
+
+        state.is_some_and(|s| self.final_states.contains(&s))
+    }
+}
@@ -0,0 +1,123 @@
+/// One past the biggest unicode code point, `0x10FFFF`
+pub const CODE_POINT_END: u32 = 0x110000;
+
+/// A set of code points stored as sorted, disjoint and non-adjacent half-open intervals `[start, end)`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct IntervalSet {
+    intervals: Vec<(u32, u32)>,
+}
+
+impl IntervalSet {
+    pub fn new(intervals: impl IntoIterator<Item = (u32, u32)>) -> Self {
+        let mut intervals: Vec<(u32, u32)> = intervals.into_iter().filter(|(start, end)| start < end).collect();
+        intervals.sort_unstable();
+
+        let mut merged: Vec<(u32, u32)> = Vec::with_capacity(intervals.len());
+        for (start, end) in intervals {
+            match merged.last_mut() {
+                Some((_, last_end)) if start <= *last_end => *last_end = (*last_end).max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+
+        Self { intervals: merged }
+    }
+
+    /// The set with all the characters between `start` and `end` inclusive
+    pub fn from_range(start: char, end: char) -> Self {
+        Self::new([(start as u32, end as u32 + 1)])
+    }
+
+    /// The set with all the characters of a table of sorted and disjoint inclusive ranges
+    pub fn from_table(table: &[(char, char)]) -> Self {
+        Self::new(table.iter().map(|&(start, end)| (start as u32, end as u32 + 1)))
+    }
+
+    pub fn intervals(&self) -> &[(u32, u32)] {
+        &self.intervals
+    }
+
+    pub fn contains(&self, symbol: char) -> bool {
+        self.contains_code_point(symbol as u32)
+    }
+
+    pub fn contains_code_point(&self, code_point: u32) -> bool {
+        // The first interval that ends after the code point is the only one that can contain it
+        let index = self.intervals.partition_point(|&(_, end)| end <= code_point);
+
+        self.intervals.get(index).is_some_and(|&(start, _)| start <= code_point)
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> Self {
+        Self::new(self.intervals.iter().chain(&other.intervals).copied())
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> Self {
+        let mut intervals = Vec::new();
+        let (mut i, mut j) = (0, 0);
+
+        while let (Some(&(start, end)), Some(&(other_start, other_end))) =
+            (self.intervals.get(i), other.intervals.get(j))
+        {
+            if start.max(other_start) < end.min(other_end) {
+                intervals.push((start.max(other_start), end.min(other_end)));
+            }
+
+            // The interval that ends first can't overlap with any other interval of the other set
+            if end < other_end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        Self { intervals }
+    }
+
+    /// All the code points that aren't in this set
+    pub fn complement(&self) -> Self {
+        let mut intervals = Vec::with_capacity(self.intervals.len() + 1);
+        let mut start = 0;
+
+        for &(interval_start, interval_end) in &self.intervals {
+            if start < interval_start {
+                intervals.push((start, interval_start));
+            }
+            start = interval_end;
+        }
+
+        if start < CODE_POINT_END {
+            intervals.push((start, CODE_POINT_END));
+        }
+
+        Self { intervals }
+    }
+}
+
+/// Split possibly overlapping intervals into the smallest disjoint intervals that cover the same code points,
+/// where every one of the original intervals is the union of some of the new ones.
+///
+/// [a-z] and [d-f] => [a-c], [d-f] and [g-z]
+pub fn partition(intervals: impl IntoIterator<Item = (u32, u32)>) -> Vec<(u32, u32)> {
+    let mut boundaries: Vec<(u32, i32)> = intervals
+        .into_iter()
+        .filter(|(start, end)| start < end)
+        .flat_map(|(start, end)| [(start, 1), (end, -1)])
+        .collect();
+    boundaries.sort_unstable();
+
+    let mut partition = Vec::new();
+    // How many of the original intervals contain the code points after the current boundary
+    let mut depth = 0;
+    let mut previous_boundary = 0;
+
+    for (boundary, delta) in boundaries {
+        if depth > 0 && previous_boundary < boundary {
+            partition.push((previous_boundary, boundary));
+        }
+        depth += delta;
+        previous_boundary = boundary;
+    }
+
+    partition
+}
@@ -0,0 +1,220 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use super::automata::{Automata, SizeLimits, State, TransitionType};
+use super::parser::{Error, RegexAST};
+
+/// A transition of the cache that wasn't computed yet
+const UNKNOWN: usize = usize::MAX;
+/// The state without NFA states, once it is reached the text can't be matched
+const DEAD: usize = usize::MAX - 1;
+
+/// The DFA states computed so far, the start state is always the first one
+#[derive(Debug)]
+struct Cache {
+    /// The NFA states of every DFA state, the index is the DFA state
+    states: Vec<BTreeSet<State>>,
+    ids: HashMap<BTreeSet<State>, usize>,
+    final_states: Vec<bool>,
+    /// The destination of every DFA state by every letter of the alphabet, one row per state
+    transitions: Vec<usize>,
+    /// How many times the cache was full and had to be cleared
+    clears: usize,
+}
+
+/// A DFA built from the NFA while matching: a state is the set of NFA states reached by a prefix of the text, and
+/// it is only computed with the subset construction the first time a text reaches it. Unlike the DFA built by
+/// [`Automata::from_regex_expr`], the compile time doesn't depend on the number of DFA states, so it never needs
+/// the NFA simulation as a fallback.
+///
+/// The states are cached, at most as many as the DFA size limit, when the cache is full it is cleared and the
+/// states are computed again. A search takes a cache from a pool and gives it back when it ends, so the threads
+/// that search at the same time don't wait for each other, every one of them gets its own cache.
+#[derive(Debug)]
+pub struct LazyDfa {
+    /// An NFA without assertion transitions
+    nfa: Automata,
+    /// The disjoint ranges of the transitions of the NFA, every DFA state has a transition for each of them
+    alphabet: Vec<(u32, u32)>,
+    start_states: BTreeSet<State>,
+    cache_size: usize,
+    /// The caches that aren't used by a search, a new one is created when a search finds it empty
+    caches: Mutex<Vec<Cache>>,
+}
+
+impl Clone for LazyDfa {
+    fn clone(&self) -> Self {
+        Self::new(self.nfa.clone(), self.cache_size)
+    }
+}
+
+impl LazyDfa {
+    pub fn new(nfa: Automata, cache_size: usize) -> Self {
+        let alphabet = nfa
+            .alphabet()
+            .into_iter()
+            .filter_map(|letter| match letter {
+                TransitionType::Range(start, end) => Some((start, end)),
+                TransitionType::Epsilon | TransitionType::Assertion(_) | TransitionType::Bytes(..) => None,
+            })
+            .collect();
+        let start_states = nfa.eclosure(BTreeSet::from([nfa.start_state()]));
+
+        let lazy_dfa = Self {
+            nfa,
+            alphabet,
+            start_states,
+            cache_size,
+            caches: Mutex::new(Vec::new()),
+        };
+        let cache = lazy_dfa.new_cache();
+        lazy_dfa.lock_caches().push(cache);
+
+        lazy_dfa
+    }
+
+    /// Build the lazy DFA of the regex, only the NFA is built now. Fails if the NFA exceeds its size limit.
+    pub fn from_regex_expr(expr: RegexAST, limits: SizeLimits) -> Result<Self, Error> {
+        Ok(Self::new(
+            Automata::nfa_from_regex_expr(expr, limits.nfa_states)?,
+            limits.dfa_states,
+        ))
+    }
+
+    pub fn nfa(&self) -> &Automata {
+        &self.nfa
+    }
+
+    /// The number of DFA states in the largest cache of the pool
+    pub fn cached_states(&self) -> usize {
+        self.lock_caches()
+            .iter()
+            .map(|cache| cache.states.len())
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// How many times the caches of the pool were full and had to be cleared
+    pub fn cache_clears(&self) -> usize {
+        self.lock_caches().iter().map(|cache| cache.clears).sum()
+    }
+
+    /// The number of caches in the pool, at most one for each of the searches that ran at the same time
+    pub fn caches(&self) -> usize {
+        self.lock_caches().len()
+    }
+
+    pub fn validate_str(&self, text: &str) -> bool {
+        self.validate(text.chars().map(u32::from))
+    }
+
+    /// Like [`LazyDfa::validate_str`], for a sequence of code points, or of bytes for the byte automata
+    pub fn validate(&self, symbols: impl Iterator<Item = u32>) -> bool {
+        // The pool is only locked to take the cache and to give it back, not while the text is read
+        let mut cache = self.lock_caches().pop().unwrap_or_else(|| self.new_cache());
+        let is_match = self.validate_with_cache(&mut cache, symbols);
+        self.lock_caches().push(cache);
+
+        is_match
+    }
+
+    fn validate_with_cache(&self, cache: &mut Cache, symbols: impl Iterator<Item = u32>) -> bool {
+        let mut state = 0;
+
+        for symbol in symbols {
+            let Some(letter) = self.letter(symbol) else {
+                return false;
+            };
+
+            state = match cache.transitions[state * self.alphabet.len() + letter] {
+                UNKNOWN => self.compute_transition(cache, state, letter),
+                dest_state => dest_state,
+            };
+            if state == DEAD {
+                return false;
+            }
+        }
+
+        cache.final_states[state]
+    }
+
+    /// The index of the letter of the alphabet that contains the symbol
+    fn letter(&self, symbol: u32) -> Option<usize> {
+        let letter = self.alphabet.partition_point(|&(_, end)| end <= symbol);
+
+        self.alphabet
+            .get(letter)
+            .filter(|&&(start, _)| start <= symbol)
+            .map(|_| letter)
+    }
+
+    /// Find the destination of the state by the letter with the subset construction and cache it
+    fn compute_transition(&self, cache: &mut Cache, state: usize, letter: usize) -> usize {
+        let (start, _) = self.alphabet[letter];
+        let dest_states = self.nfa.eclosure(self.nfa.step(&cache.states[state], start));
+        if dest_states.is_empty() {
+            cache.transitions[state * self.alphabet.len() + letter] = DEAD;
+            return DEAD;
+        }
+        if let Some(&dest_state) = cache.ids.get(&dest_states) {
+            cache.transitions[state * self.alphabet.len() + letter] = dest_state;
+            return dest_state;
+        }
+
+        // The state is lost when the cache is cleared, so the transition is only cached when there is room for the
+        // new state. The next time the state is reached it is computed again.
+        if cache.states.len() >= self.cache_size {
+            self.clear_cache(cache);
+            return self.add_state(cache, dest_states);
+        }
+
+        let dest_state = self.add_state(cache, dest_states);
+        cache.transitions[state * self.alphabet.len() + letter] = dest_state;
+        dest_state
+    }
+
+    /// A cache with only the start state
+    fn new_cache(&self) -> Cache {
+        let mut cache = Cache {
+            states: Vec::new(),
+            ids: HashMap::new(),
+            final_states: Vec::new(),
+            transitions: Vec::new(),
+            clears: 0,
+        };
+        self.clear_cache(&mut cache);
+
+        cache
+    }
+
+    fn add_state(&self, cache: &mut Cache, states: BTreeSet<State>) -> usize {
+        let state = cache.states.len();
+
+        cache.final_states.push(!(&states & self.nfa.final_states()).is_empty());
+        cache.ids.insert(states.clone(), state);
+        cache.states.push(states);
+        let rows = cache.transitions.len() + self.alphabet.len();
+        cache.transitions.resize(rows, UNKNOWN);
+
+        state
+    }
+
+    /// Remove every state from the cache, except the start state
+    fn clear_cache(&self, cache: &mut Cache) {
+        if !cache.states.is_empty() {
+            cache.clears += 1;
+        }
+
+        cache.states.clear();
+        cache.ids.clear();
+        cache.final_states.clear();
+        cache.transitions.clear();
+        self.add_state(cache, self.start_states.clone());
+    }
+
+    fn lock_caches(&self) -> MutexGuard<'_, Vec<Cache>> {
+        // The pool is only changed by pushing and popping whole caches, so it can still be used if a thread
+        // panicked while holding it
+        self.caches.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
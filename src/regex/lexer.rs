@@ -1,40 +1,151 @@
 use std::ops::Range;
 
+use super::class::{PerlClass, PosixClass};
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum TokenTypes {
     Symbol(char),
     Union,
     ClosurePlus,
     ClosureStar,
+    /// a? => zero or one a
+    Optional,
+    /// a{m}, a{m,} or a{m,n}, a `None` max means that the repetition is unbounded
+    Repetition(u32, Option<u32>),
     Concatenation,
     OpenParenthesis,
+    /// (?: => opens a group that doesn't capture
+    NonCapturingGroup,
+    /// (?<name> or (?P<name> => opens a capture group, holds the char range `[start, end)` of the name
+    NamedGroup(usize, usize),
+    /// (?i-s) => enables the first flags and disables the second ones until the end of the enclosing group
+    SetFlags(Flags, Flags),
+    /// (?i-s: => opens a group that doesn't capture, where the first flags are enabled and the second ones disabled
+    FlagGroup(Flags, Flags),
+    /// A "(?" that doesn't open a valid group, the parser reports it as an error
+    InvalidGroup(GroupError),
     CloseParenthesis,
     OpenBracket,
     CloseBracket,
+    /// A "^" right after the "[" of a character class
+    Negation,
     Dash,
     Dot,
+    /// ^ => start of the text, or of a line in multi-line mode
+    Caret,
+    /// $ => end of the text, or of a line in multi-line mode
+    Dollar,
+    /// \A => start of the text
+    StartOfText,
+    /// \z => end of the text
+    EndOfText,
+    /// \b => boundary between a word character and a non-word character
+    WordBoundary,
+    /// \B => anywhere except a word boundary
+    NotWordBoundary,
+    /// \d, \s, \w => the Perl class, or its negation \D, \S, \W when the flag is true
+    PerlClass(PerlClass, bool),
+    /// [:alpha:] => the POSIX class inside a character class, or its negation [:^alpha:] when the flag is true
+    PosixClass(PosixClass, bool),
+    /// A [:name:] where the name isn't a POSIX class, the parser reports it as an error
+    InvalidPosixClass,
+    /// A [:name that isn't closed by :], the parser reports it as an error
+    UnclosedPosixClass,
+    /// \p{name} or \pN => the Unicode general category or script, holds the char range `[start, end)` of the
+    /// name, and its negation \P{name} when the flag is true
+    UnicodeClass(usize, usize, bool),
+    /// An escape sequence that couldn't be lexed, the parser reports it as an error
+    InvalidEscape(EscapeError),
     Eof,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EscapeError {
+    /// A `\` at the end of the regex
+    Dangling,
+    /// A `\` followed by a character that doesn't have a special meaning, e.g. `\q`
+    Unknown(char),
+    /// A `\x` not followed by two hex digits, or a `\u` not followed by `{hex digits}`
+    MalformedHex,
+    /// A hex escape that isn't a valid unicode scalar value, e.g. `\u{D800}`
+    InvalidCodePoint(u32),
+    /// A `\p` not followed by `{name}` or a letter
+    MalformedUnicodeClass,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GroupError {
+    /// A name that is empty, has invalid characters or isn't closed by `>`, e.g. `(?<1a>` or `(?<a`
+    InvalidName,
+    /// A `(?P` that isn't followed by `<name>`
+    UnclosedName,
+    /// A `(?` followed by something that isn't `:`, `<name>`, `P<name>`, `flags)` or `flags:`
+    Unknown,
+    /// A character that isn't one of the flags `i`, `m`, `s` and `x`
+    UnknownFlag(char),
+    /// A flag, or the `-` before the disabled flags, that appears more than once in the same group
+    DuplicateFlag(char),
+}
+
+/// The flags that change how a part of the regex is parsed, set with `(?flags)` or `(?flags:...)`
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Flags {
+    /// i => symbols and classes also match the characters with the same simple case folding
+    pub case_insensitive: bool,
+    /// m => `^` and `$` match at the start and the end of every line
+    pub multi_line: bool,
+    /// s => `.` also matches `\n`
+    pub dot_matches_new_line: bool,
+    /// x => whitespace is ignored and `#` starts a comment until the end of the line
+    pub verbose: bool,
+}
+
+impl Flags {
+    /// These flags after enabling the flags set in `enabled` and disabling the ones set in `disabled`
+    pub fn apply(self, enabled: Flags, disabled: Flags) -> Flags {
+        let change = |flag: bool, enable: bool, disable: bool| (flag || enable) && !disable;
+
+        Flags {
+            case_insensitive: change(
+                self.case_insensitive,
+                enabled.case_insensitive,
+                disabled.case_insensitive,
+            ),
+            multi_line: change(self.multi_line, enabled.multi_line, disabled.multi_line),
+            dot_matches_new_line: change(
+                self.dot_matches_new_line,
+                enabled.dot_matches_new_line,
+                disabled.dot_matches_new_line,
+            ),
+            verbose: change(self.verbose, enabled.verbose, disabled.verbose),
+        }
+    }
+
+    fn flag_mut(&mut self, flag: char) -> Option<&mut bool> {
+        match flag {
+            'i' => Some(&mut self.case_insensitive),
+            'm' => Some(&mut self.multi_line),
+            's' => Some(&mut self.dot_matches_new_line),
+            'x' => Some(&mut self.verbose),
+            _ => None,
+        }
+    }
+}
+
 impl TokenTypes {
     fn get_token_type(symbol: char) -> TokenTypes {
         match symbol {
             '*' => TokenTypes::ClosureStar,
             '+' => TokenTypes::ClosurePlus,
+            '?' => TokenTypes::Optional,
             '|' => TokenTypes::Union,
             '(' => TokenTypes::OpenParenthesis,
             ')' => TokenTypes::CloseParenthesis,
             '[' => TokenTypes::OpenBracket,
             ']' => TokenTypes::CloseBracket,
             '.' => TokenTypes::Dot,
-            _ => TokenTypes::Symbol(symbol),
-        }
-    }
-
-    // Inside of a "[]" all characters, except ']', are `TokenTypes::Symbol`
-    fn get_token_type_for_character_classs(symbol: char) -> TokenTypes {
-        match symbol {
-            ']' => TokenTypes::CloseBracket,
+            '^' => TokenTypes::Caret,
+            '$' => TokenTypes::Dollar,
             _ => TokenTypes::Symbol(symbol),
         }
     }
@@ -59,6 +170,7 @@ impl Token {
 
 #[derive(Debug)]
 pub struct Lexer {
+    chars: Vec<char>,
     tokens: Vec<Token>,
     index: usize,
 }
@@ -66,11 +178,17 @@ pub struct Lexer {
 impl Lexer {
     pub fn new(input: &str) -> Self {
         Self {
+            chars: input.chars().collect(),
             tokens: tokenize_regex_str(input),
             index: 0,
         }
     }
 
+    /// Get the characters of the input between the char indices `start` and `end`
+    pub fn slice(&self, start: usize, end: usize) -> String {
+        self.chars[start..end].iter().collect()
+    }
+
     /// Get a Token and advance the Token stream pointer by 1
     pub fn next_token(&mut self) -> Token {
         // If we reach the end of tokens vector always return the EOF Token
@@ -94,37 +212,65 @@ pub fn tokenize_regex_str(regex: &str) -> Vec<Token> {
     let mut tokens: Vec<Token> = vec![];
     let chars: Vec<char> = regex.chars().collect();
     let mut index = 0;
+    let mut previous_token_type = None;
+    // The flags of every open group, the lexer only needs them to know where the verbose mode is enabled
+    let mut flags = vec![Flags::default()];
 
     while index < chars.len() {
-        let symbol = chars[index];
-
-        let mut current_token_type = TokenTypes::get_token_type(symbol);
-        tokens.push(Token::new(current_token_type, index, index + 1));
-
-        if current_token_type == TokenTypes::OpenBracket {
-            handle_character_class(&mut tokens, &chars, &mut index, &mut current_token_type);
-        }
-
-        if let Some(&next_symbol) = chars.get(index + 1) {
-            let next_token_type = TokenTypes::get_token_type(next_symbol);
-
-            if matches!(
-                current_token_type,
-                TokenTypes::Symbol(_)
-                    | TokenTypes::Dot
-                    | TokenTypes::CloseParenthesis
-                    | TokenTypes::CloseBracket
-                    | TokenTypes::ClosurePlus
-                    | TokenTypes::ClosureStar
-            ) && matches!(
-                next_token_type,
-                TokenTypes::Symbol(_) | TokenTypes::Dot | TokenTypes::OpenParenthesis | TokenTypes::OpenBracket
-            ) {
-                // Since the Concatenation is implicit we don't care about it's position
-                tokens.push(Token::new(TokenTypes::Concatenation, 0, 0));
+        if flags.last().unwrap().verbose {
+            // In verbose mode whitespace is ignored and "#" starts a comment until the end of the line
+            match chars[index] {
+                symbol if symbol.is_whitespace() => {
+                    index += 1;
+                    continue;
+                }
+                '#' => {
+                    while index < chars.len() && chars[index] != '\n' {
+                        index += 1;
+                    }
+                    continue;
+                }
+                _ => {}
             }
         }
 
+        let token = match chars[index] {
+            // An escaped whitespace is a literal in verbose mode
+            '\\' if flags.last().unwrap().verbose
+                && chars.get(index + 1).is_some_and(|symbol| symbol.is_whitespace()) =>
+            {
+                index += 1;
+                Token::new(TokenTypes::Symbol(chars[index]), index - 1, index + 1)
+            }
+            '\\' => lex_escape(&chars, &mut index),
+            '{' => lex_repetition(&chars, &mut index),
+            '(' => lex_group(&chars, &mut index),
+            symbol => Token::new(TokenTypes::get_token_type(symbol), index, index + 1),
+        };
+
+        if previous_token_type.is_some_and(|previous| needs_concatenation(previous, token.ty)) {
+            // Since the Concatenation is implicit we don't care about it's position
+            tokens.push(Token::new(TokenTypes::Concatenation, 0, 0));
+        }
+        tokens.push(token);
+
+        let current_flags = *flags.last().unwrap();
+        match token.ty {
+            TokenTypes::OpenBracket => handle_character_class(&mut tokens, &chars, &mut index),
+            TokenTypes::OpenParenthesis | TokenTypes::NonCapturingGroup | TokenTypes::NamedGroup(..) => {
+                flags.push(current_flags)
+            }
+            TokenTypes::FlagGroup(enabled, disabled) => flags.push(current_flags.apply(enabled, disabled)),
+            TokenTypes::SetFlags(enabled, disabled) => {
+                *flags.last_mut().unwrap() = current_flags.apply(enabled, disabled)
+            }
+            TokenTypes::CloseParenthesis if flags.len() > 1 => {
+                flags.pop();
+            }
+            _ => {}
+        }
+
+        previous_token_type = tokens.last().map(|token| token.ty);
         index += 1;
     }
 
@@ -133,46 +279,402 @@ pub fn tokenize_regex_str(regex: &str) -> Vec<Token> {
     tokens
 }
 
-/// [abc] or [a-zA-Z]
-fn handle_character_class(
-    tokens: &mut Vec<Token>,
-    chars: &[char],
-    index: &mut usize,
-    current_token_type: &mut TokenTypes,
-) {
-    while !matches!(current_token_type, TokenTypes::CloseBracket | TokenTypes::Eof) {
-        *index += 1;
-        if let Some(&symbol) = chars.get(*index) {
-            *current_token_type = TokenTypes::get_token_type_for_character_classs(symbol);
-            tokens.push(Token::new(*current_token_type, *index, *index + 1));
-
-            if let Some(&next_symbol) = chars.get(*index + 1) {
-                let next_token_type = TokenTypes::get_token_type_for_character_classs(next_symbol);
-
-                if let Some(&next_next_symbol) = chars.get(*index + 2) {
-                    let next_next_token_type = TokenTypes::get_token_type_for_character_classs(next_next_symbol);
-
-                    // Only make dash a token-type if is between two symbols
-                    if matches!(current_token_type, TokenTypes::Symbol(_))
-                        && matches!(next_token_type, TokenTypes::Symbol('-'))
-                        && matches!(next_next_token_type, TokenTypes::Symbol(_))
-                    {
-                        tokens.push(Token::new(TokenTypes::Dash, *index + 1, *index + 2));
-                        *index += 1;
-                        continue;
+fn needs_concatenation(current_token_type: TokenTypes, next_token_type: TokenTypes) -> bool {
+    matches!(
+        current_token_type,
+        TokenTypes::Symbol(_)
+            | TokenTypes::Dot
+            | TokenTypes::Caret
+            | TokenTypes::Dollar
+            | TokenTypes::StartOfText
+            | TokenTypes::EndOfText
+            | TokenTypes::WordBoundary
+            | TokenTypes::NotWordBoundary
+            | TokenTypes::PerlClass(..)
+            | TokenTypes::UnicodeClass(..)
+            | TokenTypes::InvalidEscape(_)
+            | TokenTypes::CloseParenthesis
+            | TokenTypes::CloseBracket
+            | TokenTypes::ClosurePlus
+            | TokenTypes::ClosureStar
+            | TokenTypes::Optional
+            | TokenTypes::Repetition(..)
+            | TokenTypes::SetFlags(..)
+    ) && matches!(
+        next_token_type,
+        TokenTypes::Symbol(_)
+            | TokenTypes::Dot
+            | TokenTypes::Caret
+            | TokenTypes::Dollar
+            | TokenTypes::StartOfText
+            | TokenTypes::EndOfText
+            | TokenTypes::WordBoundary
+            | TokenTypes::NotWordBoundary
+            | TokenTypes::PerlClass(..)
+            | TokenTypes::UnicodeClass(..)
+            | TokenTypes::OpenParenthesis
+            | TokenTypes::NonCapturingGroup
+            | TokenTypes::NamedGroup(..)
+            | TokenTypes::InvalidGroup(_)
+            | TokenTypes::SetFlags(..)
+            | TokenTypes::FlagGroup(..)
+            | TokenTypes::OpenBracket
+            | TokenTypes::InvalidEscape(_)
+    )
+}
+
+/// Lex the escape sequence starting at `index`, which must point to a `\`. When this function returns `index`
+/// points to the last character of the escape sequence.
+///
+/// `\*`, `\n`, `\x41`, `\u{1F600}`, `\p{Greek}` or `\A`
+fn lex_escape(chars: &[char], index: &mut usize) -> Token {
+    let start = *index;
+    let Some(&symbol) = chars.get(start + 1) else {
+        return Token::new(TokenTypes::InvalidEscape(EscapeError::Dangling), start, start + 1);
+    };
+    *index += 1;
+
+    let token_type = match symbol {
+        'n' => TokenTypes::Symbol('\n'),
+        't' => TokenTypes::Symbol('\t'),
+        'r' => TokenTypes::Symbol('\r'),
+        '0' => TokenTypes::Symbol('\0'),
+        'A' => TokenTypes::StartOfText,
+        'z' => TokenTypes::EndOfText,
+        'd' => TokenTypes::PerlClass(PerlClass::Digit, false),
+        'D' => TokenTypes::PerlClass(PerlClass::Digit, true),
+        's' => TokenTypes::PerlClass(PerlClass::Space, false),
+        'S' => TokenTypes::PerlClass(PerlClass::Space, true),
+        'w' => TokenTypes::PerlClass(PerlClass::Word, false),
+        'W' => TokenTypes::PerlClass(PerlClass::Word, true),
+        'p' => lex_unicode_class(chars, index, false),
+        'P' => lex_unicode_class(chars, index, true),
+        'b' => TokenTypes::WordBoundary,
+        'B' => TokenTypes::NotWordBoundary,
+        'x' => lex_hex_escape(chars, index, Some(2)),
+        'u' => {
+            if chars.get(*index + 1) == Some(&'{') {
+                *index += 1;
+                lex_hex_escape(chars, index, None)
+            } else {
+                TokenTypes::InvalidEscape(EscapeError::MalformedHex)
+            }
+        }
+        // Any ASCII punctuation can be escaped, this way all the metacharacters become literals
+        s if s.is_ascii_punctuation() => TokenTypes::Symbol(s),
+        s => TokenTypes::InvalidEscape(EscapeError::Unknown(s)),
+    };
+
+    Token::new(token_type, start, *index + 1)
+}
+
+/// Lex the name of a `\p{name}` or `\pN` escape, `index` must point to the `p`
+fn lex_unicode_class(chars: &[char], index: &mut usize, negated: bool) -> TokenTypes {
+    match chars.get(*index + 1) {
+        Some('{') => {
+            let name_start = *index + 2;
+            let Some(length) = chars[name_start..].iter().position(|&symbol| symbol == '}') else {
+                return TokenTypes::InvalidEscape(EscapeError::MalformedUnicodeClass);
+            };
+            if length == 0 {
+                return TokenTypes::InvalidEscape(EscapeError::MalformedUnicodeClass);
+            }
+
+            *index = name_start + length;
+            TokenTypes::UnicodeClass(name_start, name_start + length, negated)
+        }
+        Some(symbol) if symbol.is_alphabetic() => {
+            *index += 1;
+            TokenTypes::UnicodeClass(*index, *index + 1, negated)
+        }
+        _ => TokenTypes::InvalidEscape(EscapeError::MalformedUnicodeClass),
+    }
+}
+
+/// Lex the hexadecimal digits of a `\x` or `\u{...}` escape. With `fixed_len` exactly that many digits are
+/// read, otherwise the digits are delimited by the braces that `index` is pointing at.
+fn lex_hex_escape(chars: &[char], index: &mut usize, fixed_len: Option<usize>) -> TokenTypes {
+    let mut digits = String::new();
+
+    match fixed_len {
+        Some(len) => {
+            while digits.len() < len {
+                match chars.get(*index + 1) {
+                    Some(&digit) if digit.is_ascii_hexdigit() => digits.push(digit),
+                    _ => return TokenTypes::InvalidEscape(EscapeError::MalformedHex),
+                }
+                *index += 1;
+            }
+        }
+        None => loop {
+            match chars.get(*index + 1) {
+                Some('}') => {
+                    *index += 1;
+                    break;
+                }
+                Some(&digit) if digit.is_ascii_hexdigit() && digits.len() < 6 => digits.push(digit),
+                _ => return TokenTypes::InvalidEscape(EscapeError::MalformedHex),
+            }
+            *index += 1;
+        },
+    }
+
+    if digits.is_empty() {
+        return TokenTypes::InvalidEscape(EscapeError::MalformedHex);
+    }
+
+    let code_point = u32::from_str_radix(&digits, 16).unwrap();
+    char::from_u32(code_point).map_or(
+        TokenTypes::InvalidEscape(EscapeError::InvalidCodePoint(code_point)),
+        TokenTypes::Symbol,
+    )
+}
+
+/// Lex the counted repetition starting at `index`, which must point to a `{`. When this function returns
+/// `index` points to the closing `}`.
+///
+/// `{m}`, `{m,}` or `{m,n}`, if the braces don't follow one of these forms the `{` is a literal symbol.
+fn lex_repetition(chars: &[char], index: &mut usize) -> Token {
+    let start = *index;
+    let mut position = start + 1;
+
+    let read_number = |position: &mut usize| {
+        let mut number: Option<u32> = None;
+        while let Some(digit) = chars.get(*position).and_then(|symbol| symbol.to_digit(10)) {
+            // Numbers that don't fit are saturated, they will be rejected by the repetition limit anyway
+            number = Some(number.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+            *position += 1;
+        }
+        number
+    };
+
+    let Some(min) = read_number(&mut position) else {
+        return Token::new(TokenTypes::Symbol('{'), start, start + 1);
+    };
+
+    let max = match chars.get(position) {
+        Some('}') => Some(min),
+        Some(',') => {
+            position += 1;
+            read_number(&mut position)
+        }
+        _ => return Token::new(TokenTypes::Symbol('{'), start, start + 1),
+    };
+
+    if chars.get(position) != Some(&'}') {
+        return Token::new(TokenTypes::Symbol('{'), start, start + 1);
+    }
+
+    *index = position;
+    Token::new(TokenTypes::Repetition(min, max), start, position + 1)
+}
+
+/// Lex the opening of the group starting at `index`, which must point to a `(`. When this function returns
+/// `index` points to the last character of the opening.
+///
+/// `(`, `(?:`, `(?<name>`, `(?P<name>`, `(?flags)` or `(?flags:`
+fn lex_group(chars: &[char], index: &mut usize) -> Token {
+    let start = *index;
+    if chars.get(start + 1) != Some(&'?') {
+        return Token::new(TokenTypes::OpenParenthesis, start, start + 1);
+    }
+
+    match (chars.get(start + 2), chars.get(start + 3)) {
+        (Some(':'), _) => {
+            *index = start + 2;
+            Token::new(TokenTypes::NonCapturingGroup, start, start + 3)
+        }
+        (Some('<'), _) => lex_group_name(chars, index, start + 3),
+        (Some('P'), Some('<')) => lex_group_name(chars, index, start + 4),
+        (Some('P'), _) => {
+            *index = start + 2;
+            Token::new(TokenTypes::InvalidGroup(GroupError::UnclosedName), start, start + 3)
+        }
+        (Some(symbol), _) if symbol.is_ascii_alphabetic() || *symbol == '-' => lex_flags(chars, index),
+        _ => {
+            *index = start + 1;
+            Token::new(TokenTypes::InvalidGroup(GroupError::Unknown), start, start + 2)
+        }
+    }
+}
+
+/// Lex the flags of a `(?flags)` or `(?flags:` group starting at `index`, which must point to the `(`. The flags
+/// are letters like `i` or `-s`, where the flags after the `-` are disabled.
+fn lex_flags(chars: &[char], index: &mut usize) -> Token {
+    let start = *index;
+    let (mut enabled, mut disabled) = (Flags::default(), Flags::default());
+    let mut negated = false;
+    let mut any_flag = false;
+    let mut position = start + 2;
+
+    let token_type = loop {
+        let invalid_flag = |error| Token::new(TokenTypes::InvalidGroup(error), position, position + 1);
+
+        match chars.get(position) {
+            Some(')') if any_flag => break TokenTypes::SetFlags(enabled, disabled),
+            Some(':') if any_flag => break TokenTypes::FlagGroup(enabled, disabled),
+            Some(')' | ':') | None => {
+                *index = position.min(chars.len() - 1);
+                return Token::new(TokenTypes::InvalidGroup(GroupError::Unknown), start, start + 2);
+            }
+            Some('-') if negated => {
+                *index = position;
+                return invalid_flag(GroupError::DuplicateFlag('-'));
+            }
+            Some('-') => negated = true,
+            Some(&symbol) => {
+                let (enabled_flag, disabled_flag) = match (enabled.flag_mut(symbol), disabled.flag_mut(symbol)) {
+                    (Some(enabled_flag), Some(disabled_flag)) => (enabled_flag, disabled_flag),
+                    _ => {
+                        *index = position;
+                        return invalid_flag(GroupError::UnknownFlag(symbol));
                     }
+                };
+                if *enabled_flag || *disabled_flag {
+                    *index = position;
+                    return invalid_flag(GroupError::DuplicateFlag(symbol));
                 }
 
-                if matches!(current_token_type, TokenTypes::Symbol(_))
-                    && matches!(next_token_type, TokenTypes::Symbol(_))
-                {
-                    // Inside brackets the Union is implicit
-                    tokens.push(Token::new(TokenTypes::Union, 0, 0));
+                *(if negated { disabled_flag } else { enabled_flag }) = true;
+                any_flag = true;
+            }
+        }
+        position += 1;
+    };
+
+    *index = position;
+    Token::new(token_type, start, position + 1)
+}
+
+/// Lex the name of a group that starts at `name_start` and ends with a `>`. A name starts with a letter or `_`,
+/// followed by letters, digits or `_`.
+fn lex_group_name(chars: &[char], index: &mut usize, name_start: usize) -> Token {
+    let start = *index;
+    let mut name_end = name_start;
+    while chars
+        .get(name_end)
+        .is_some_and(|&symbol| symbol.is_alphanumeric() || symbol == '_')
+    {
+        name_end += 1;
+    }
+
+    let starts_with_digit = chars.get(name_start).is_some_and(|symbol| symbol.is_numeric());
+    if name_start == name_end || starts_with_digit || chars.get(name_end) != Some(&'>') {
+        *index = name_end.min(chars.len()) - 1;
+        return Token::new(TokenTypes::InvalidGroup(GroupError::InvalidName), start, name_end);
+    }
+
+    *index = name_end;
+    Token::new(TokenTypes::NamedGroup(name_start, name_end), start, name_end + 1)
+}
+
+/// Lex the POSIX class starting at `index`, which must point to the `[` of a `[:`. When this function returns
+/// `index` points to the last character of the class.
+///
+/// `[:alpha:]` or `[:^alpha:]`, if there isn't a closing `:]` after the name the class is unclosed.
+fn lex_posix_class(chars: &[char], index: &mut usize) -> Token {
+    let start = *index;
+    let negated = chars.get(start + 2) == Some(&'^');
+    let name_start = if negated { start + 3 } else { start + 2 };
+
+    let mut name_end = name_start;
+    while chars.get(name_end).is_some_and(|symbol| symbol.is_ascii_alphabetic()) {
+        name_end += 1;
+    }
+
+    if chars.get(name_end) != Some(&':') || chars.get(name_end + 1) != Some(&']') {
+        *index = name_end - 1;
+        return Token::new(TokenTypes::UnclosedPosixClass, start, name_end);
+    }
+
+    *index = name_end + 1;
+    let name: String = chars[name_start..name_end].iter().collect();
+    let token_type = PosixClass::from_name(&name).map_or(TokenTypes::InvalidPosixClass, |class| {
+        TokenTypes::PosixClass(class, negated)
+    });
+
+    Token::new(token_type, start, *index + 1)
+}
+
+/// [abc], [a-zA-Z] or [^abc]
+fn handle_character_class(tokens: &mut Vec<Token>, chars: &[char], index: &mut usize) {
+    let mut items: Vec<Token> = vec![];
+
+    // Only a leading "^" negates the character class, anywhere else it is a literal
+    if chars.get(*index + 1) == Some(&'^') {
+        *index += 1;
+        tokens.push(Token::new(TokenTypes::Negation, *index, *index + 1));
+    }
+
+    loop {
+        *index += 1;
+        let item = match chars.get(*index) {
+            Some(']') => {
+                items.push(Token::new(TokenTypes::CloseBracket, *index, *index + 1));
+                break;
+            }
+            Some('\\') => {
+                let mut item = lex_escape(chars, index);
+                // Assertions don't match any character, so they can't be part of a character class
+                if matches!(
+                    item.ty,
+                    TokenTypes::StartOfText
+                        | TokenTypes::EndOfText
+                        | TokenTypes::WordBoundary
+                        | TokenTypes::NotWordBoundary
+                ) {
+                    item.ty = TokenTypes::InvalidEscape(EscapeError::Unknown(chars[*index]));
                 }
+                item
             }
-        } else {
-            *current_token_type = TokenTypes::Eof;
-            tokens.push(Token::new(*current_token_type, *index + 1, *index + 1));
+            Some('[') if chars.get(*index + 1) == Some(&':') => lex_posix_class(chars, index),
+            // Whether the dash is a range or a literal is decided below, when we know its neighbours
+            Some('-') => Token::new(TokenTypes::Dash, *index, *index + 1),
+            Some(&symbol) => Token::new(TokenTypes::Symbol(symbol), *index, *index + 1),
+            None => break,
         };
+        items.push(item);
+    }
+
+    let is_symbol = |item: Option<&Token>| {
+        matches!(
+            item.map(|item| item.ty),
+            Some(TokenTypes::Symbol(_) | TokenTypes::InvalidEscape(_))
+        )
+    };
+    // Classes like \w can be part of the union, but they can't be the bound of a range
+    let is_item = |item: Option<&Token>| {
+        is_symbol(item)
+            || matches!(
+                item.map(|item| item.ty),
+                Some(
+                    TokenTypes::PerlClass(..)
+                        | TokenTypes::UnicodeClass(..)
+                        | TokenTypes::PosixClass(..)
+                        | TokenTypes::InvalidPosixClass
+                        | TokenTypes::UnclosedPosixClass
+                )
+            )
+    };
+
+    let mut range_end = None;
+    for (i, &item) in items.iter().enumerate() {
+        let mut item = item;
+
+        if item.ty == TokenTypes::Dash {
+            // Only make dash a token-type if is between two symbols, and the lhs isn't the end of another range
+            if i > 0 && is_symbol(items.get(i - 1)) && range_end != Some(i - 1) && is_symbol(items.get(i + 1)) {
+                tokens.push(item);
+                range_end = Some(i + 1);
+                continue;
+            }
+            item.ty = TokenTypes::Symbol('-');
+        }
+
+        if is_item(Some(&item)) && is_item(tokens.last()) {
+            // Inside brackets the Union is implicit
+            tokens.push(Token::new(TokenTypes::Union, 0, 0));
+        }
+        tokens.push(item);
     }
 }
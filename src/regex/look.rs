@@ -0,0 +1,143 @@
+use std::cmp::Ordering;
+
+use super::interval::IntervalSet;
+use super::parser::Assertion;
+use super::unicode_tables::perl_word::PERL_WORD;
+
+/// The kind of the character on one side of a position of the haystack, this is everything the assertions need
+/// to know about the characters around the position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Look {
+    /// There isn't any character, the position is the start or the end of the haystack
+    Boundary,
+    LineFeed,
+    /// An ASCII character matched by `\w`
+    Word,
+    /// A character matched by the Unicode `\w` that isn't ASCII, it is only a word character for the Unicode `\b`
+    UnicodeWord,
+    Other,
+}
+
+impl Look {
+    pub const ALL: [Look; 5] = [
+        Look::Boundary,
+        Look::LineFeed,
+        Look::Word,
+        Look::UnicodeWord,
+        Look::Other,
+    ];
+
+    pub fn of(symbol: Option<char>) -> Self {
+        match symbol {
+            None => Look::Boundary,
+            Some('\n') => Look::LineFeed,
+            Some(symbol) if symbol.is_ascii_alphanumeric() || symbol == '_' => Look::Word,
+            Some(symbol) if is_word_character(symbol) => Look::UnicodeWord,
+            Some(_) => Look::Other,
+        }
+    }
+
+    /// The kind of the character before the byte offset `at`
+    pub fn behind(haystack: &str, at: usize) -> Self {
+        Look::of(haystack[..at].chars().next_back())
+    }
+
+    /// The kind of the character after the byte offset `at`
+    pub fn ahead(haystack: &str, at: usize) -> Self {
+        Look::of(haystack[at..].chars().next())
+    }
+
+    /// The kind of the character before the byte offset `at` of a haystack that can have invalid UTF-8, a byte
+    /// that isn't part of a valid character is like any other character that isn't a line feed or a word character.
+    /// The offset must not split a valid character, see [`splits_character`].
+    pub fn behind_bytes(haystack: &[u8], at: usize) -> Self {
+        if at == 0 {
+            return Look::Boundary;
+        }
+
+        // A character is at most 4 bytes long
+        (1..=at.min(4))
+            .find_map(|len| character(&haystack[at - len..at]))
+            .map_or(Look::Other, |symbol| Look::of(Some(symbol)))
+    }
+
+    /// The kind of the character after the byte offset `at` of a haystack that can have invalid UTF-8, like
+    /// [`Look::behind_bytes`]
+    pub fn ahead_bytes(haystack: &[u8], at: usize) -> Self {
+        if at == haystack.len() {
+            return Look::Boundary;
+        }
+
+        (1..=(haystack.len() - at).min(4))
+            .find_map(|len| character(&haystack[at..at + len]))
+            .map_or(Look::Other, |symbol| Look::of(Some(symbol)))
+    }
+
+    /// All the characters of this kind
+    pub fn characters(self) -> IntervalSet {
+        let line_feed = IntervalSet::from_range('\n', '\n');
+        let word = IntervalSet::from_table(PERL_WORD);
+        let ascii = IntervalSet::new([(0, 0x80)]);
+        match self {
+            Look::Boundary => IntervalSet::default(),
+            Look::LineFeed => line_feed,
+            Look::Word => word.intersection(&ascii),
+            Look::UnicodeWord => word.intersection(&ascii.complement()),
+            Look::Other => line_feed.union(&word).complement(),
+        }
+    }
+
+    fn is_word(self) -> bool {
+        matches!(self, Look::Word | Look::UnicodeWord)
+    }
+
+    fn is_ascii_word(self) -> bool {
+        self == Look::Word
+    }
+}
+
+/// Check if the byte offset `at` is between the bytes of a valid UTF-8 encoded character of the haystack. The
+/// assertions never match at such an offset, as there isn't a character boundary there.
+pub fn splits_character(haystack: &[u8], at: usize) -> bool {
+    // A character is at most 4 bytes long, so it starts at most 3 bytes before the offset
+    (at.saturating_sub(3)..at)
+        .any(|start| (at + 1..=haystack.len().min(start + 4)).any(|end| character(&haystack[start..end]).is_some()))
+}
+
+/// The character encoded by the bytes, if they are the UTF-8 encoding of a single character
+fn character(bytes: &[u8]) -> Option<char> {
+    let mut chars = std::str::from_utf8(bytes).ok()?.chars();
+
+    chars.next().filter(|_| chars.next().is_none())
+}
+
+/// Check if the character is matched by the Unicode `\w`
+pub fn is_word_character(symbol: char) -> bool {
+    PERL_WORD
+        .binary_search_by(|&(start, end)| {
+            if end < symbol {
+                Ordering::Less
+            } else if symbol < start {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+impl Assertion {
+    /// Check if the assertion holds at a position, given the kinds of the characters `behind` and `ahead` of it
+    pub fn is_satisfied(self, behind: Look, ahead: Look) -> bool {
+        match self {
+            Assertion::StartText => behind == Look::Boundary,
+            Assertion::EndText => ahead == Look::Boundary,
+            Assertion::StartLine => matches!(behind, Look::Boundary | Look::LineFeed),
+            Assertion::EndLine => matches!(ahead, Look::Boundary | Look::LineFeed),
+            Assertion::WordBoundary => behind.is_word() != ahead.is_word(),
+            Assertion::NotWordBoundary => behind.is_word() == ahead.is_word(),
+            Assertion::WordBoundaryAscii => behind.is_ascii_word() != ahead.is_ascii_word(),
+            Assertion::NotWordBoundaryAscii => behind.is_ascii_word() == ahead.is_ascii_word(),
+        }
+    }
+}
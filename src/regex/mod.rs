@@ -1,36 +1,506 @@
+use std::{borrow::Cow, collections::HashMap, ops::Range, sync::Arc};
+
 use self::{
-    automata::{Automata, Dfa},
+    automata::{Automata, Matcher, SizeLimits},
     debug::AutomataPrinter,
-    parser::parse_regex,
+    lazy::LazyDfa,
+    nfa::Program,
+    parser::{parse_regex_with_config, ParserConfig},
+    pikevm::{Cache, CachePool, PikeVM, PoolGuard},
 };
 
 mod automata;
+pub mod bytes;
+mod class;
 mod debug;
-mod helper;
+mod dense;
+mod interval;
+mod lazy;
 mod lexer;
+mod look;
+mod nfa;
 mod parser;
+mod pikevm;
+mod replace;
+mod unicode_tables;
+mod utf8;
+#[cfg(test)]
 mod tests;
 
+pub use automata::{Engine, Minimization};
+#[doc(hidden)]
+pub use dense::SparseDfa;
 pub use parser::Error;
+pub use replace::{NoExpand, Replacer};
+pub use unicode_tables::version::UNICODE_VERSION;
 
 #[derive(Debug)]
 pub struct Regex {
-    pub automaton: Dfa,
+    pub automaton: Matcher,
+    program: Program,
+    /// The memory of the searches of `program`, reused by all of them
+    caches: CachePool,
+    /// The index of every named capture group, shared with the `Captures` created by this regex
+    group_indices: Arc<HashMap<String, usize>>,
 }
 
 impl Regex {
     pub fn new(re: &str) -> Result<Self, Error> {
-        Ok(Self {
-            automaton: Automata::from_regex_expr(parse_regex(re)?),
-        })
+        RegexBuilder::new(re).build()
     }
 
+    /// Check if the whole `text` is matched by the regex.
     pub fn is_match(&self, text: &str) -> bool {
         self.automaton.validate_str(text)
     }
 
+    /// The engine used by [`Regex::is_match`]. It is the DFA, unless the DFA would exceed the size limit set with
+    /// [`RegexBuilder::dfa_size_limit`], then the NFA is simulated instead. With [`RegexBuilder::lazy_dfa`] it is
+    /// always the lazy DFA.
+    pub fn engine(&self) -> Engine {
+        self.automaton.engine()
+    }
+
+    /// The DFA of [`Regex::is_match`] with the ranges of every state searched instead of the dense table, the
+    /// baseline of the `dense_table` benchmark. `None` if the engine isn't the DFA.
+    #[doc(hidden)]
+    pub fn sparse_dfa(&self) -> Option<SparseDfa> {
+        match &self.automaton {
+            Matcher::Dfa(dfa) => Some(SparseDfa::new(dfa.dense())),
+            Matcher::Nfa(_) | Matcher::LazyDfa(_) => None,
+        }
+    }
+
+    /// Find the leftmost-first match in `haystack`. Unlike [`Regex::is_match`], the match can be any substring of
+    /// the haystack: the one that starts first is returned, and between the ones starting at the same position
+    /// the alternatives of the regex are preferred from left to right, e.g. `a|ab` finds `a` in `ab`.
+    pub fn find<'h>(&self, haystack: &'h str) -> Option<Match<'h>> {
+        self.find_at(haystack, 0)
+    }
+
+    /// Iterate over all the non-overlapping leftmost-first matches in `haystack`. An empty match right after the
+    /// end of the previous match is skipped, and the search continues from the next character.
+    pub fn find_iter<'r, 'h>(&'r self, haystack: &'h str) -> Matches<'r, 'h> {
+        Matches {
+            captures: self.captures_iter(haystack),
+        }
+    }
+
+    /// Find the leftmost-first match in `haystack` like [`Regex::find`], and also where every capture group
+    /// matched. A group that didn't participate in the match has no span, e.g. the group 2 of `(a)|(b)` in `a`.
+    pub fn captures<'h>(&self, haystack: &'h str) -> Option<Captures<'h>> {
+        self.captures_at(&mut self.caches.get(&self.program), haystack, 0)
+    }
+
+    /// The names of all the capture groups by index, including the group 0. The group 0 and the unnamed groups
+    /// yield `None`.
+    pub fn capture_names(&self) -> impl Iterator<Item = Option<&str>> {
+        self.program.capture_names.iter().map(Option::as_deref)
+    }
+
+    /// Iterate over the capture groups of all the non-overlapping matches in `haystack`, the matches are the same
+    /// ones returned by [`Regex::find_iter`].
+    pub fn captures_iter<'r, 'h>(&'r self, haystack: &'h str) -> CaptureMatches<'r, 'h> {
+        CaptureMatches {
+            regex: self,
+            cache: self.caches.get(&self.program),
+            haystack,
+            at: 0,
+            last_match_end: None,
+        }
+    }
+
+    /// Iterate over the substrings of `haystack` delimited by the matches of the regex.
+    pub fn split<'r, 'h>(&'r self, haystack: &'h str) -> Split<'r, 'h> {
+        Split {
+            matches: self.find_iter(haystack),
+            last: 0,
+        }
+    }
+
+    /// Like [`Regex::split`], but yields at most `limit` substrings, the last one holds the rest of `haystack`.
+    pub fn splitn<'r, 'h>(&'r self, haystack: &'h str, limit: usize) -> SplitN<'r, 'h> {
+        SplitN {
+            splits: self.split(haystack),
+            limit,
+        }
+    }
+
+    /// Replace the leftmost-first match in `haystack` with the replacement built by `replacer`, see [`Replacer`].
+    /// The haystack is borrowed when there is no match.
+    pub fn replace<'h, R: Replacer>(&self, haystack: &'h str, replacer: R) -> Cow<'h, str> {
+        self.replacen(haystack, 1, replacer)
+    }
+
+    /// Replace all the non-overlapping matches in `haystack`, the matches are the same ones returned by
+    /// [`Regex::find_iter`].
+    pub fn replace_all<'h, R: Replacer>(&self, haystack: &'h str, replacer: R) -> Cow<'h, str> {
+        self.replacen(haystack, 0, replacer)
+    }
+
+    /// Replace at most `limit` matches in `haystack`, a `limit` of 0 replaces all of them.
+    pub fn replacen<'h, R: Replacer>(&self, haystack: &'h str, limit: usize, mut replacer: R) -> Cow<'h, str> {
+        let limit = if limit == 0 { usize::MAX } else { limit };
+        let mut captures_iter = self.captures_iter(haystack).take(limit).peekable();
+        if captures_iter.peek().is_none() {
+            return Cow::Borrowed(haystack);
+        }
+
+        let mut replaced = String::with_capacity(haystack.len());
+        let mut last = 0;
+        for captures in captures_iter {
+            // The group 0 always exists in the captures of a match
+            let found = captures.get(0).unwrap();
+            replaced.push_str(&haystack[last..found.start()]);
+            replacer.replace_append(&captures, &mut replaced);
+            last = found.end();
+        }
+        replaced.push_str(&haystack[last..]);
+
+        Cow::Owned(replaced)
+    }
+
+    /// Find the leftmost-first match that starts at or after the byte offset `start`
+    fn find_at<'h>(&self, haystack: &'h str, start: usize) -> Option<Match<'h>> {
+        self.captures_at(&mut self.caches.get(&self.program), haystack, start)?
+            .get(0)
+    }
+
+    /// Find the captures of the leftmost-first match that starts at or after the byte offset `start`, the memory of
+    /// the search is reused from `cache`
+    fn captures_at<'h>(&self, cache: &mut Cache, haystack: &'h str, start: usize) -> Option<Captures<'h>> {
+        let slots = PikeVM::new(&self.program).search(cache, haystack, start)?;
+
+        Some(Captures {
+            haystack,
+            slots,
+            group_indices: Arc::clone(&self.group_indices),
+        })
+    }
+
     pub fn debug_save_automata_to_file(&self, filename: &str) {
-        let printer = AutomataPrinter::new(&self.automaton);
+        let printer = match &self.automaton {
+            Matcher::Dfa(dfa) => AutomataPrinter::new(dfa),
+            Matcher::Nfa(nfa) => AutomataPrinter::new(nfa),
+            Matcher::LazyDfa(lazy_dfa) => AutomataPrinter::new(lazy_dfa.nfa()),
+        };
         printer.save_to_file(filename);
     }
 }
+
+/// Build a [`Regex`] with non-default compile options.
+#[derive(Debug, Clone)]
+pub struct RegexBuilder {
+    pattern: String,
+    config: ParserConfig,
+    limits: SizeLimits,
+    minimization: Minimization,
+    lazy_dfa: bool,
+}
+
+impl RegexBuilder {
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            config: ParserConfig::default(),
+            limits: SizeLimits::default(),
+            minimization: Minimization::default(),
+            lazy_dfa: false,
+        }
+    }
+
+    /// Make `\d`, `\s` and `\w` match any Unicode digit, whitespace or word character, when disabled they only
+    /// match ASCII characters. `\b` and `\B` use the same word characters as `\w`. Enabled by default.
+    pub fn unicode(&mut self, yes: bool) -> &mut Self {
+        self.config.unicode = yes;
+        self
+    }
+
+    /// Make `^` and `$` match at the start and the end of every line, instead of only at the start and the end of
+    /// the text. `\A` and `\z` always match only at the start and the end of the text.
+    pub fn multi_line(&mut self, yes: bool) -> &mut Self {
+        self.config.multi_line = yes;
+        self
+    }
+
+    /// Make every symbol and class also match the characters with the same Unicode simple case folding, e.g. `k`
+    /// matches `k`, `K` and the Kelvin sign `\u{212A}`. It can also be enabled inside the pattern with `(?i)`.
+    pub fn case_insensitive(&mut self, yes: bool) -> &mut Self {
+        self.config.case_insensitive = yes;
+        self
+    }
+
+    /// Make `.` match any character, instead of any character except `\n`. It can also be enabled inside the
+    /// pattern with `(?s)`.
+    pub fn dot_matches_new_line(&mut self, yes: bool) -> &mut Self {
+        self.config.dot_matches_new_line = yes;
+        self
+    }
+
+    /// Set the biggest bound accepted in a counted repetition like `a{2,5}`, the default is 1000.
+    pub fn repetition_limit(&mut self, limit: u32) -> &mut Self {
+        self.config.repetition_limit = limit;
+        self
+    }
+
+    /// Set how deep the groups and repetition operators can be nested, e.g. `((a))` and `(a+)` have a depth of 2.
+    /// The default is 250.
+    pub fn nest_limit(&mut self, limit: u32) -> &mut Self {
+        self.config.nest_limit = limit;
+        self
+    }
+
+    /// Set the most states of the NFA built from the pattern, a bigger NFA fails with [`Error::TooBig`]. With
+    /// [`RegexBuilder::build_bytes`] it is also the limit of the NFA that reads the bytes of the characters. The
+    /// default is 100000.
+    pub fn nfa_size_limit(&mut self, limit: usize) -> &mut Self {
+        self.limits.nfa_states = limit;
+        self
+    }
+
+    /// Set the most states of every DFA built while compiling the pattern. When the DFA would be bigger,
+    /// [`Regex::is_match`] simulates the NFA instead, see [`Regex::engine`]. It is also the size of the cache of the
+    /// lazy DFA. The default is 10000.
+    pub fn dfa_size_limit(&mut self, limit: usize) -> &mut Self {
+        self.limits.dfa_states = limit;
+        self
+    }
+
+    /// Set the algorithm that minimizes the DFA, both build the same DFA. The default is
+    /// [`Minimization::Brzozowski`], [`Minimization::Hopcroft`] avoids determinizing the reversed NFA, that can be
+    /// exponentially bigger than the DFA, e.g. for `(a|b){20}a(a|b)*`.
+    pub fn minimization(&mut self, minimization: Minimization) -> &mut Self {
+        self.minimization = minimization;
+        self
+    }
+
+    /// Build the DFA while matching instead of when compiling the pattern, only the states reached by the texts
+    /// are created. It is faster for patterns used on a few short texts. The states are cached, and the cache is
+    /// cleared when it has as many states as the DFA size limit. Disabled by default.
+    pub fn lazy_dfa(&mut self, yes: bool) -> &mut Self {
+        self.lazy_dfa = yes;
+        self
+    }
+
+    /// Make `.` match any byte instead of any character in the regex built by [`RegexBuilder::build_bytes`], it
+    /// still doesn't match `\n` unless [`RegexBuilder::dot_matches_new_line`] is enabled. The regex built by
+    /// [`RegexBuilder::build`] only matches valid UTF-8, so this is ignored there. Disabled by default.
+    pub fn dot_matches_any_byte(&mut self, yes: bool) -> &mut Self {
+        self.config.dot_matches_any_byte = yes;
+        self
+    }
+
+    pub fn build(&self) -> Result<Regex, Error> {
+        let config = ParserConfig {
+            dot_matches_any_byte: false,
+            ..self.config
+        };
+        let tree = parse_regex_with_config(&self.pattern, &config)?;
+        // The automaton checks the size limits, so it is built before the program that has the same size as the NFA
+        let automaton = if self.lazy_dfa {
+            Matcher::LazyDfa(LazyDfa::from_regex_expr(tree.clone(), self.limits)?)
+        } else {
+            Automata::from_regex_expr(tree.clone(), self.limits, self.minimization)?
+        };
+        let program = Program::new(tree);
+        let group_indices = program
+            .capture_names
+            .iter()
+            .enumerate()
+            .filter_map(|(index, name)| Some((name.clone()?, index)))
+            .collect();
+
+        Ok(Regex {
+            automaton,
+            program,
+            caches: CachePool::default(),
+            group_indices: Arc::new(group_indices),
+        })
+    }
+}
+
+/// A match of a [`Regex`] in a haystack, the positions are byte offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match<'h> {
+    haystack: &'h str,
+    start: usize,
+    end: usize,
+}
+
+impl<'h> Match<'h> {
+    fn new(haystack: &'h str, start: usize, end: usize) -> Self {
+        Self { haystack, start, end }
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn as_str(&self) -> &'h str {
+        &self.haystack[self.range()]
+    }
+}
+
+/// The spans of the capture groups of a match, the group 0 is always the whole match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Captures<'h> {
+    haystack: &'h str,
+    slots: Vec<Option<usize>>,
+    group_indices: Arc<HashMap<String, usize>>,
+}
+
+impl<'h> Captures<'h> {
+    /// The match of the group `index`, or `None` if the group didn't participate in the match or doesn't exist
+    pub fn get(&self, index: usize) -> Option<Match<'h>> {
+        let start = (*self.slots.get(2 * index)?)?;
+        let end = (*self.slots.get(2 * index + 1)?)?;
+
+        Some(Match::new(self.haystack, start, end))
+    }
+
+    /// The match of the group called `name`, or `None` if the group didn't participate in the match or doesn't
+    /// exist
+    pub fn name(&self, name: &str) -> Option<Match<'h>> {
+        self.get(*self.group_indices.get(name)?)
+    }
+
+    /// Append `template` to `dst` replacing the references to capture groups like `$1` or `${name}`, the syntax
+    /// is described in [`Replacer`].
+    pub fn expand(&self, template: &str, dst: &mut String) {
+        replace::expand(self, template, dst);
+    }
+
+    /// The number of groups, including the group 0
+    pub fn len(&self) -> usize {
+        self.slots.len() / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Iterator over the capture groups of the matches of a [`Regex`], created by [`Regex::captures_iter`].
+#[derive(Debug)]
+pub struct CaptureMatches<'r, 'h> {
+    regex: &'r Regex,
+    /// The memory of the searches, shared by all the matches and given back to the regex at the end
+    cache: PoolGuard<'r>,
+    haystack: &'h str,
+    at: usize,
+    last_match_end: Option<usize>,
+}
+
+impl<'r, 'h> Iterator for CaptureMatches<'r, 'h> {
+    type Item = Captures<'h>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.at > self.haystack.len() {
+                return None;
+            }
+
+            let captures = self.regex.captures_at(&mut self.cache, self.haystack, self.at)?;
+            let found = captures.get(0)?;
+
+            if found.is_empty() && self.last_match_end == Some(found.end()) {
+                // Step over the next character, so the search keeps starting at a UTF-8 boundary
+                self.at = found.end() + self.haystack[found.end()..].chars().next().map_or(1, char::len_utf8);
+                continue;
+            }
+
+            self.at = found.end();
+            self.last_match_end = Some(found.end());
+
+            return Some(captures);
+        }
+    }
+}
+
+/// Iterator over the matches of a [`Regex`], created by [`Regex::find_iter`].
+#[derive(Debug)]
+pub struct Matches<'r, 'h> {
+    captures: CaptureMatches<'r, 'h>,
+}
+
+impl<'r, 'h> Iterator for Matches<'r, 'h> {
+    type Item = Match<'h>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.captures.next()?.get(0)
+    }
+}
+
+/// Iterator over the substrings between the matches of a [`Regex`], created by [`Regex::split`].
+#[derive(Debug)]
+pub struct Split<'r, 'h> {
+    matches: Matches<'r, 'h>,
+    last: usize,
+}
+
+impl<'r, 'h> Split<'r, 'h> {
+    /// The part of the haystack that hasn't been yielded yet
+    fn remainder(&mut self) -> Option<&'h str> {
+        let haystack = self.matches.captures.haystack;
+        if self.last > haystack.len() {
+            return None;
+        }
+
+        let remainder = &haystack[self.last..];
+        self.last = haystack.len() + 1;
+
+        Some(remainder)
+    }
+}
+
+impl<'r, 'h> Iterator for Split<'r, 'h> {
+    type Item = &'h str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.matches.next() {
+            Some(found) => {
+                let substring = &self.matches.captures.haystack[self.last..found.start()];
+                self.last = found.end();
+
+                Some(substring)
+            }
+            None => self.remainder(),
+        }
+    }
+}
+
+/// Iterator over at most `limit` substrings between the matches of a [`Regex`], created by [`Regex::splitn`].
+#[derive(Debug)]
+pub struct SplitN<'r, 'h> {
+    splits: Split<'r, 'h>,
+    limit: usize,
+}
+
+impl<'r, 'h> Iterator for SplitN<'r, 'h> {
+    type Item = &'h str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.limit == 0 {
+            return None;
+        }
+
+        self.limit -= 1;
+        if self.limit > 0 {
+            self.splits.next()
+        } else {
+            self.splits.remainder()
+        }
+    }
+}
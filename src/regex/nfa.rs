@@ -0,0 +1,216 @@
+use super::automata::{character_class_intervals, expand_repetition};
+use super::interval::{IntervalSet, CODE_POINT_END};
+use super::parser::{Assertion, BinaryOp, RegexAST, UnaryOp};
+use super::utf8::utf8_sequences;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    /// Consume one character contained in the set, or one byte in the byte programs
+    Ranges(IntervalSet),
+    /// Continue in both instructions, the first one has priority over the second one
+    Split(usize, usize),
+    Jump(usize),
+    /// Record the current position of the input in a slot
+    Save(usize),
+    /// Continue in the next instruction only if the assertion holds at the current position of the input
+    Assertion(Assertion),
+    Match,
+}
+
+impl Instruction {
+    /// Check if the instruction consumes `symbol`, the code point of a character or a byte
+    pub fn matches(&self, symbol: u32) -> bool {
+        match self {
+            Instruction::Ranges(ranges) => ranges.contains_code_point(symbol),
+            _ => false,
+        }
+    }
+}
+
+/// A Thompson NFA represented as a list of instructions, that keeps the priority between the alternatives of
+/// the regex and the positions where the match and its capture groups start and end. The slots `2 * i` and
+/// `2 * i + 1` hold the start and the end of the group `i`, where the group 0 is the whole match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+    pub slots: usize,
+    /// The name of every capture group by index, the group 0 and the unnamed groups don't have a name
+    pub capture_names: Vec<Option<String>>,
+    /// If the program reads the UTF-8 encoding of the characters one byte at a time
+    pub bytes: bool,
+}
+
+impl Program {
+    pub fn new(tree: RegexAST) -> Self {
+        Self::with_unit(tree, false)
+    }
+
+    /// Build a program that reads bytes, every set of characters becomes the alternation of the sequences of
+    /// bytes of their UTF-8 encodings
+    pub fn new_bytes(tree: RegexAST) -> Self {
+        Self::with_unit(tree, true)
+    }
+
+    fn with_unit(tree: RegexAST, bytes: bool) -> Self {
+        let mut capture_names = vec![None];
+        collect_capture_names(&tree, &mut capture_names);
+
+        let mut program = Self {
+            instructions: vec![Instruction::Save(0)],
+            slots: 2 * capture_names.len(),
+            capture_names,
+            bytes,
+        };
+        program.compile(tree);
+        program.instructions.push(Instruction::Save(1));
+        program.instructions.push(Instruction::Match);
+
+        program
+    }
+
+    /// Follows the same structure as `build_automata_from_ast`, the epsilon transitions become `Split` and `Jump`
+    /// instructions, where the first alternative of a `Split` is the one that should be preferred.
+    fn compile(&mut self, tree: RegexAST) {
+        match tree {
+            RegexAST::Binary(lhs, BinaryOp::Concatenation, rhs) => {
+                self.compile(*lhs);
+                self.compile(*rhs);
+            }
+            RegexAST::Binary(lhs, BinaryOp::Union, rhs) => {
+                let split = self.push_placeholder();
+                self.compile(*lhs);
+                let jump = self.push_placeholder();
+                let rhs_start = self.instructions.len();
+                self.compile(*rhs);
+
+                self.instructions[split] = Instruction::Split(split + 1, rhs_start);
+                self.instructions[jump] = Instruction::Jump(self.instructions.len());
+            }
+            // When the body can match the empty string, `Split(body, end); body; Jump(split)` would prefer leaving
+            // the loop after an empty iteration over matching the body, e.g. `(?:\b|c)*` would find `c` in "c"
+            // instead of the empty string, so it is compiled as `(?:x+)?` that tries the body first
+            RegexAST::Unary(lhs, UnaryOp::ClosureStar) if can_match_empty(&lhs) => self.compile(RegexAST::Unary(
+                Box::new(RegexAST::Unary(lhs, UnaryOp::ClosurePlus)),
+                UnaryOp::Optional,
+            )),
+            RegexAST::Unary(lhs, UnaryOp::ClosureStar) => {
+                let split = self.push_placeholder();
+                self.compile(*lhs);
+                self.instructions.push(Instruction::Jump(split));
+
+                self.instructions[split] = Instruction::Split(split + 1, self.instructions.len());
+            }
+            RegexAST::Unary(lhs, UnaryOp::ClosurePlus) => {
+                let start = self.instructions.len();
+                self.compile(*lhs);
+                self.instructions
+                    .push(Instruction::Split(start, self.instructions.len() + 1));
+            }
+            RegexAST::Unary(lhs, UnaryOp::Optional) => {
+                let split = self.push_placeholder();
+                self.compile(*lhs);
+
+                self.instructions[split] = Instruction::Split(split + 1, self.instructions.len());
+            }
+            RegexAST::Unary(lhs, UnaryOp::Repetition { min, max }) => self.compile(expand_repetition(*lhs, min, max)),
+            RegexAST::Symbol(symbol) => self.compile_ranges(IntervalSet::from_range(symbol, symbol)),
+            RegexAST::CharacterClass(char_class_type) => {
+                self.compile_ranges(character_class_intervals(char_class_type))
+            }
+            RegexAST::AnyCharacter => self.compile_ranges(IntervalSet::new([(0, CODE_POINT_END)])),
+            RegexAST::Bytes(bytes) => self.instructions.push(Instruction::Ranges(bytes)),
+            RegexAST::EmptyString => {}
+            RegexAST::Assertion(assertion) => self.instructions.push(Instruction::Assertion(assertion)),
+            RegexAST::Group(lhs, index, _) => {
+                self.instructions.push(Instruction::Save(2 * index));
+                self.compile(*lhs);
+                self.instructions.push(Instruction::Save(2 * index + 1));
+            }
+        }
+    }
+
+    /// Consume one character of the set. The byte programs match one of the sequences of bytes of its UTF-8
+    /// encodings instead, every sequence is an alternative and is matched one byte at a time.
+    fn compile_ranges(&mut self, ranges: IntervalSet) {
+        if !self.bytes {
+            self.instructions.push(Instruction::Ranges(ranges));
+            return;
+        }
+
+        let sequences: Vec<_> = ranges
+            .intervals()
+            .iter()
+            .flat_map(|&(start, end)| utf8_sequences(start, end))
+            .collect();
+        if sequences.is_empty() {
+            // An empty set never matches
+            self.instructions.push(Instruction::Ranges(ranges));
+            return;
+        }
+
+        let mut jumps = Vec::new();
+        for (index, sequence) in sequences.iter().enumerate() {
+            let split = (index + 1 < sequences.len()).then(|| self.push_placeholder());
+            for &(start, end) in sequence {
+                self.instructions.push(Instruction::Ranges(IntervalSet::new([(
+                    u32::from(start),
+                    u32::from(end) + 1,
+                )])));
+            }
+
+            if let Some(split) = split {
+                jumps.push(self.push_placeholder());
+                self.instructions[split] = Instruction::Split(split + 1, self.instructions.len());
+            }
+        }
+
+        for jump in jumps {
+            self.instructions[jump] = Instruction::Jump(self.instructions.len());
+        }
+    }
+
+    /// Push an instruction that will be replaced once its targets are known
+    fn push_placeholder(&mut self) -> usize {
+        self.instructions.push(Instruction::Match);
+        self.instructions.len() - 1
+    }
+}
+
+/// Check if the tree matches the empty string somewhere, ignoring if its assertions can hold
+fn can_match_empty(tree: &RegexAST) -> bool {
+    match tree {
+        RegexAST::Binary(lhs, BinaryOp::Concatenation, rhs) => can_match_empty(lhs) && can_match_empty(rhs),
+        RegexAST::Binary(lhs, BinaryOp::Union, rhs) => can_match_empty(lhs) || can_match_empty(rhs),
+        RegexAST::Unary(_, UnaryOp::ClosureStar | UnaryOp::Optional) => true,
+        RegexAST::Unary(lhs, UnaryOp::ClosurePlus) => can_match_empty(lhs),
+        RegexAST::Unary(lhs, UnaryOp::Repetition { min, .. }) => *min == 0 || can_match_empty(lhs),
+        RegexAST::Group(lhs, _, _) => can_match_empty(lhs),
+        RegexAST::EmptyString | RegexAST::Assertion(_) => true,
+        RegexAST::Symbol(_) | RegexAST::CharacterClass(_) | RegexAST::AnyCharacter | RegexAST::Bytes(_) => false,
+    }
+}
+
+/// Store the name of every group of the tree at its index, every group is included even if it can never match,
+/// e.g. `(a){0}`
+fn collect_capture_names(tree: &RegexAST, names: &mut Vec<Option<String>>) {
+    match tree {
+        RegexAST::Binary(lhs, _, rhs) => {
+            collect_capture_names(lhs, names);
+            collect_capture_names(rhs, names);
+        }
+        RegexAST::Unary(lhs, _) => collect_capture_names(lhs, names),
+        RegexAST::Group(lhs, index, name) => {
+            if names.len() <= *index {
+                names.resize(index + 1, None);
+            }
+            names[*index] = name.clone();
+            collect_capture_names(lhs, names);
+        }
+        RegexAST::Symbol(_)
+        | RegexAST::CharacterClass(_)
+        | RegexAST::EmptyString
+        | RegexAST::AnyCharacter
+        | RegexAST::Bytes(_)
+        | RegexAST::Assertion(_) => {}
+    }
+}
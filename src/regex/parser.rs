@@ -1,9 +1,16 @@
-use super::lexer::{Lexer, TokenTypes};
+use std::collections::HashSet;
+
+use super::automata::character_class_intervals;
+use super::class::{case_fold, unicode_property, PerlClass, PosixClass};
+use super::interval::IntervalSet;
+use super::lexer::{EscapeError, Flags, GroupError, Lexer, Token, TokenTypes};
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum Error {
     Syntax(String),
     InvalidRange(String),
+    /// The automaton built from the regex would exceed one of the size limits
+    TooBig(String),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -20,6 +27,31 @@ pub enum UnaryOp {
     ClosurePlus,
     /// a* => Zero or more a
     ClosureStar,
+    /// a? => Zero or one a
+    Optional,
+    /// a{m,n} => Between m and n a, a `None` max means there is no upper bound
+    Repetition { min: u32, max: Option<u32> },
+}
+
+/// A condition on the position between two characters, it doesn't consume any character
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Assertion {
+    /// ^ or \A => the start of the text
+    StartText,
+    /// $ or \z => the end of the text
+    EndText,
+    /// ^ in multi-line mode => the start of the text or right after a "\n"
+    StartLine,
+    /// $ in multi-line mode => the end of the text or right before a "\n"
+    EndLine,
+    /// \b => between a word character and a character that isn't one, or the start or end of the text
+    WordBoundary,
+    /// \B => anywhere \b doesn't match
+    NotWordBoundary,
+    /// \b when Unicode is disabled => like `WordBoundary`, but only the ASCII characters are word characters
+    WordBoundaryAscii,
+    /// \B when Unicode is disabled => anywhere the ASCII \b doesn't match
+    NotWordBoundaryAscii,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -39,12 +71,22 @@ pub enum RegexAST {
     EmptyString,
     /// . => matches any character
     AnyCharacter,
+    /// . => matches any byte in the set, it replaces `AnyCharacter` in the byte regex when the dot matches any byte
+    Bytes(IntervalSet),
+    /// (a) or (?<name>a) => matches a and records the span of the match in the capture group with the given index
+    /// and optional name, the groups are numbered from 1 by the position of their opening parenthesis.
+    Group(Box<RegexAST>, usize, Option<String>),
+    Assertion(Assertion),
 }
 
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub enum CharacterClassType {
     Single(char),
     Binary(Box<CharacterClassType>, CharacterClassBinaryOp, Box<CharacterClassType>),
+    /// [^abc] => matches any character except the ones in the inner character class
+    Negated(Box<CharacterClassType>),
+    /// \d, \w, etc => matches any of the characters in the set
+    Set(IntervalSet),
 }
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
@@ -78,24 +120,150 @@ impl Operation {
     }
 }
 
+/// The biggest bound accepted by default in a counted repetition, e.g. `a{1,1000}`
+pub const DEFAULT_REPETITION_LIMIT: u32 = 1000;
+/// How deep the groups and repetition operators can be nested by default
+pub const DEFAULT_NEST_LIMIT: u32 = 250;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParserConfig {
+    /// The biggest bound accepted in a counted repetition. Every repetition is expanded into copies of its
+    /// automaton, so this stops patterns like `a{1,100000}` from creating a huge NFA.
+    pub repetition_limit: u32,
+    /// Makes `^` and `$` match at the start and the end of every line, instead of only the text
+    pub multi_line: bool,
+    /// Makes the Perl classes like `\d` match any Unicode character of the class, instead of only ASCII, and `\b`
+    /// use the Unicode word characters
+    pub unicode: bool,
+    /// Makes every symbol and class also match the characters with the same simple case folding
+    pub case_insensitive: bool,
+    /// Makes `.` also match `\n`
+    pub dot_matches_new_line: bool,
+    /// How deep the groups and repetition operators can be nested. The parser and the automata recurse into every
+    /// group and repetition, so this stops patterns like `((((...))))` or `a????...` from overflowing the stack.
+    pub nest_limit: u32,
+    /// Makes `.` match any byte instead of any character, only the byte regex can be built with it
+    pub dot_matches_any_byte: bool,
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        Self {
+            repetition_limit: DEFAULT_REPETITION_LIMIT,
+            multi_line: false,
+            unicode: true,
+            case_insensitive: false,
+            dot_matches_new_line: false,
+            nest_limit: DEFAULT_NEST_LIMIT,
+            dot_matches_any_byte: false,
+        }
+    }
+}
+
+/// The state shared by all the recursive calls while parsing a regex
+#[derive(Debug)]
+struct ParserState<'c> {
+    config: &'c ParserConfig,
+    /// How many capture groups have been opened so far
+    captures: usize,
+    /// The names of the named capture groups opened so far
+    names: HashSet<String>,
+    /// The flags of every group that is being parsed, the last ones are the flags in effect. They start as the
+    /// flags of the config, and `(?flags)` changes them until the end of the enclosing group.
+    flags: Vec<Flags>,
+}
+
+impl ParserState<'_> {
+    fn flags(&self) -> Flags {
+        *self.flags.last().unwrap()
+    }
+}
+
+#[cfg(test)]
 pub fn parse_regex(regex: &str) -> Result<RegexAST, Error> {
+    parse_regex_with_config(regex, &ParserConfig::default())
+}
+
+pub fn parse_regex_with_config(regex: &str, config: &ParserConfig) -> Result<RegexAST, Error> {
     let mut lexer = Lexer::new(regex);
-    let ast = parse_regex_expr(&mut lexer, 0)?;
+    let mut state = ParserState {
+        config,
+        captures: 0,
+        names: HashSet::new(),
+        flags: vec![Flags {
+            case_insensitive: config.case_insensitive,
+            multi_line: config.multi_line,
+            dot_matches_new_line: config.dot_matches_new_line,
+            verbose: false,
+        }],
+    };
+    let (ast, _) = parse_regex_expr(&mut lexer, 0, &mut state)?;
 
     Ok(ast)
 }
 
-fn parse_regex_expr(lexer: &mut Lexer, min_bp: u8) -> Result<RegexAST, Error> {
+/// Parse the expression until an operator that binds less than `min_bp`, returning it with its depth: how many
+/// groups and repetition operators are nested in it. The depth can't exceed the nest limit, and the chains of
+/// concatenations and unions are balanced trees, so the functions that recurse into the tree don't overflow the
+/// stack.
+fn parse_regex_expr(lexer: &mut Lexer, min_bp: u8, state: &mut ParserState) -> Result<(RegexAST, u32), Error> {
     let token = lexer.next_token();
+    let mut depth = 0;
     // Handle literals
     let mut lhs = match token.ty {
-        TokenTypes::Symbol(s) => RegexAST::Symbol(s),
-        TokenTypes::OpenParenthesis => {
+        TokenTypes::Symbol(s) => symbol(s, state),
+        TokenTypes::OpenParenthesis
+        | TokenTypes::NonCapturingGroup
+        | TokenTypes::NamedGroup(..)
+        | TokenTypes::FlagGroup(..) => {
             if let Some(TokenTypes::Eof) = lexer.peek_token().map(|token| token.ty) {
                 return Err(Error::Syntax("Invalid group: missing closing parenthesis!".to_string()));
             }
 
-            let lhs = parse_regex_expr(lexer, 0)?;
+            let name = match token.ty {
+                TokenTypes::NamedGroup(name_start, name_end) => {
+                    let name = lexer.slice(name_start, name_end);
+                    if !state.names.insert(name.clone()) {
+                        return Err(Error::Syntax(format!(
+                            "Invalid group name: the name \"{name}\" at position {} is already used by another group!",
+                            token.position().start
+                        )));
+                    }
+                    Some(name)
+                }
+                _ => None,
+            };
+            // The index is taken before parsing the inner groups, so the groups are numbered from left to right
+            let captures = matches!(token.ty, TokenTypes::OpenParenthesis | TokenTypes::NamedGroup(..));
+            let index = captures.then(|| {
+                state.captures += 1;
+                state.captures
+            });
+
+            // The flags set inside the group only last until the end of the group
+            let flags = match token.ty {
+                TokenTypes::FlagGroup(enabled, disabled) => state.flags().apply(enabled, disabled),
+                _ => state.flags(),
+            };
+            state.flags.push(flags);
+            // There are flags for the whole regex and for every group that is being parsed. The groups are checked
+            // before parsing them to stop the recursion, and again after adding the depth of their content.
+            let nest_limit = state.config.nest_limit;
+            let nest_limit_error = || {
+                Error::Syntax(format!(
+                    "Invalid group at position {}: the groups are nested deeper than the nest limit of {nest_limit}!",
+                    token.position().start,
+                ))
+            };
+            if state.flags.len() - 1 > nest_limit as usize {
+                return Err(nest_limit_error());
+            }
+            let (lhs, lhs_depth) = parse_regex_expr(lexer, 0, state)?;
+            depth = lhs_depth + 1;
+            if depth > nest_limit {
+                return Err(nest_limit_error());
+            }
+            state.flags.pop();
             if lexer.next_token().ty != TokenTypes::CloseParenthesis {
                 return Err(Error::Syntax(format!(
                     "Parenthesis at position {} doesn't have a closing parenthesis!",
@@ -103,10 +271,51 @@ fn parse_regex_expr(lexer: &mut Lexer, min_bp: u8) -> Result<RegexAST, Error> {
                 )));
             }
 
-            lhs
+            match index {
+                Some(index) => RegexAST::Group(Box::new(lhs), index, name),
+                None => lhs,
+            }
+        }
+        TokenTypes::SetFlags(enabled, disabled) => {
+            let flags = state.flags.last_mut().unwrap();
+            *flags = flags.apply(enabled, disabled);
+
+            // The flag doesn't match anything, so it can't be repeated, and it is parsed as if it wasn't there
+            let next_token = lexer.peek_token();
+            if let Some(repetition) = next_token.filter(|next_token| {
+                matches!(
+                    next_token.ty,
+                    TokenTypes::ClosureStar
+                        | TokenTypes::ClosurePlus
+                        | TokenTypes::Optional
+                        | TokenTypes::Repetition(..)
+                )
+            }) {
+                let position = token.position();
+                return Err(Error::Syntax(format!(
+                    "Invalid repetition at position {}: the flags \"{}\" don't match anything, so they can't be repeated!",
+                    repetition.position().start,
+                    lexer.slice(position.start, position.end)
+                )));
+            }
+
+            if next_token.map(|token| token.ty) == Some(TokenTypes::Concatenation) {
+                lexer.next_token();
+                let (rhs, rhs_depth) = parse_regex_expr(lexer, min_bp, state)?;
+                depth = rhs_depth;
+                rhs
+            } else {
+                RegexAST::EmptyString
+            }
         }
+        TokenTypes::InvalidGroup(error) => return Err(invalid_group_error(error, token)),
         TokenTypes::OpenBracket => {
-            let lhs = parse_character_class(lexer, 0)?;
+            let negated = lexer.peek_token().map(|token| token.ty) == Some(TokenTypes::Negation);
+            if negated {
+                lexer.next_token();
+            }
+
+            let mut lhs = parse_character_class(lexer, 0, state)?;
             if lexer.next_token().ty != TokenTypes::CloseBracket {
                 return Err(Error::Syntax(format!(
                     "Brackets at position {} doesn't have a closing brackets!",
@@ -114,10 +323,39 @@ fn parse_regex_expr(lexer: &mut Lexer, min_bp: u8) -> Result<RegexAST, Error> {
                 )));
             }
 
+            // The class is folded before the negation, so (?i)[^k] doesn't match "K" either
+            if state.flags().case_insensitive {
+                lhs = CharacterClassType::Set(case_fold(&character_class_intervals(lhs)));
+            }
+
+            if negated {
+                lhs = CharacterClassType::Negated(Box::new(lhs));
+            }
             RegexAST::CharacterClass(lhs)
         }
-        TokenTypes::Dot => RegexAST::AnyCharacter,
-        TokenTypes::Eof => return Ok(RegexAST::EmptyString),
+        TokenTypes::Dot if state.config.dot_matches_any_byte => RegexAST::Bytes(if state.flags().dot_matches_new_line {
+            IntervalSet::new([(0, 0x100)])
+        } else {
+            IntervalSet::new([(0, '\n' as u32), ('\n' as u32 + 1, 0x100)])
+        }),
+        TokenTypes::Dot if state.flags().dot_matches_new_line => RegexAST::AnyCharacter,
+        TokenTypes::Dot => RegexAST::CharacterClass(CharacterClassType::Negated(Box::new(CharacterClassType::Single(
+            '\n',
+        )))),
+        TokenTypes::PerlClass(class, negated) => RegexAST::CharacterClass(perl_class(class, negated, state)),
+        TokenTypes::UnicodeClass(name_start, name_end, negated) => {
+            RegexAST::CharacterClass(unicode_class(lexer, token, name_start, name_end, negated, state)?)
+        }
+        TokenTypes::Caret if state.flags().multi_line => RegexAST::Assertion(Assertion::StartLine),
+        TokenTypes::Dollar if state.flags().multi_line => RegexAST::Assertion(Assertion::EndLine),
+        TokenTypes::Caret | TokenTypes::StartOfText => RegexAST::Assertion(Assertion::StartText),
+        TokenTypes::Dollar | TokenTypes::EndOfText => RegexAST::Assertion(Assertion::EndText),
+        TokenTypes::WordBoundary if state.config.unicode => RegexAST::Assertion(Assertion::WordBoundary),
+        TokenTypes::NotWordBoundary if state.config.unicode => RegexAST::Assertion(Assertion::NotWordBoundary),
+        TokenTypes::WordBoundary => RegexAST::Assertion(Assertion::WordBoundaryAscii),
+        TokenTypes::NotWordBoundary => RegexAST::Assertion(Assertion::NotWordBoundaryAscii),
+        TokenTypes::InvalidEscape(error) => return Err(invalid_escape_error(error, token)),
+        TokenTypes::Eof => return Ok((RegexAST::EmptyString, 0)),
         // handle some invalid literals for this section of code
         TokenTypes::ClosureStar => return Err(Error::Syntax(
             "Invalid Closure: ClosureStar operator needs a preceding literal, e.g. \"a*\", \"(ab)*\", \"(a|c)*\"."
@@ -127,6 +365,14 @@ fn parse_regex_expr(lexer: &mut Lexer, min_bp: u8) -> Result<RegexAST, Error> {
             "Invalid Closure: ClosurePlus operator needs a preceding literal, e.g. \"a+\", \"(ab)+\", \"(a|c)+\"."
                 .to_string(),
         )),
+        TokenTypes::Optional => return Err(Error::Syntax(
+            "Invalid Optional: Optional operator needs a preceding literal, e.g. \"a?\", \"(ab)?\", \"(a|c)?\"."
+                .to_string(),
+        )),
+        TokenTypes::Repetition(..) => return Err(Error::Syntax(
+            "Invalid Repetition: Repetition operator needs a preceding literal, e.g. \"a{2}\", \"(ab){1,}\", \"(a|c){1,3}\"."
+                .to_string(),
+        )),
         TokenTypes::Union => return Err(
             Error::Syntax(
                 "Invalid Union: the union operator \"|\" needs to be between two literals, e.g. \"ab|cd\", \"a|z\", \"1*|0*\"."
@@ -135,18 +381,25 @@ fn parse_regex_expr(lexer: &mut Lexer, min_bp: u8) -> Result<RegexAST, Error> {
         ),
         TokenTypes::CloseParenthesis => return Err(Error::Syntax("Unmatched parenthesis.".to_string())),
         TokenTypes::CloseBracket => return Err(Error::Syntax("Unmatched bracket.".to_string())),
-        t => panic!("Error: Unsuported token {:?}", t),
+        _ => return Err(unexpected_token_error(lexer, token)),
     };
 
+    // The operands of the concatenation that is being parsed, and the alternatives of the union
+    let mut concatenation = Vec::new();
+    let mut alternatives = Vec::new();
+
     while let Some(token) = lexer.peek_token() {
         let op = match token.ty {
             TokenTypes::Union => Operation::Binary(BinaryOp::Union),
             TokenTypes::Concatenation => Operation::Binary(BinaryOp::Concatenation),
             TokenTypes::ClosureStar => Operation::Unary(UnaryOp::ClosureStar),
             TokenTypes::ClosurePlus => Operation::Unary(UnaryOp::ClosurePlus),
+            TokenTypes::Optional => Operation::Unary(UnaryOp::Optional),
+            TokenTypes::Repetition(min, max) => Operation::Unary(UnaryOp::Repetition { min, max }),
             TokenTypes::OpenParenthesis | TokenTypes::CloseParenthesis => Operation::Unknow(token.ty),
-            TokenTypes::Eof => return Ok(lhs),
-            t => panic!("Error: Unsuported token {:?}", t),
+            TokenTypes::Eof => break,
+            TokenTypes::CloseBracket => return Err(Error::Syntax("Unmatched bracket.".to_string())),
+            _ => return Err(unexpected_token_error(lexer, token)),
         };
 
         // Handle unary operations precedence
@@ -157,6 +410,10 @@ fn parse_regex_expr(lexer: &mut Lexer, min_bp: u8) -> Result<RegexAST, Error> {
 
             lexer.next_token();
 
+            if let UnaryOp::Repetition { min, max } = op.get_unary_op() {
+                validate_repetition(min, max, state.config)?;
+            }
+
             if let Some(TokenTypes::ClosureStar) = lexer.peek_token().map(|token| token.ty) {
                 return Err(Error::Syntax(
                     "Invalid Closure: ClosureStar operator can't be followed by another Closure Star operator"
@@ -164,11 +421,21 @@ fn parse_regex_expr(lexer: &mut Lexer, min_bp: u8) -> Result<RegexAST, Error> {
                 ));
             }
 
+            depth += 1;
+            if depth > state.config.nest_limit {
+                return Err(Error::Syntax(format!(
+                    "Invalid repetition at position {}: the groups and repetitions are nested deeper than the nest limit of {}!",
+                    token.position().start,
+                    state.config.nest_limit
+                )));
+            }
+
             lhs = RegexAST::Unary(Box::new(lhs), op.get_unary_op());
             continue;
         }
 
-        // Handle binary operations precedence
+        // Handle binary operations precedence. Both operators are left associative, so every operand is parsed by a
+        // call that stops before the next operator of the same chain, and this loop collects them.
         if let Some((l_bp, r_bp)) = infix_binding_power(op) {
             if l_bp < min_bp {
                 break;
@@ -176,47 +443,112 @@ fn parse_regex_expr(lexer: &mut Lexer, min_bp: u8) -> Result<RegexAST, Error> {
 
             lexer.next_token();
 
-            let rhs = if op.get_binary_op() == BinaryOp::Union {
+            let (rhs, rhs_depth) = if op.get_binary_op() == BinaryOp::Union {
                 // Handles the case where we have somethin like this "a|", this means we are
                 // matching "a" or the empty string.
                 if let Some(TokenTypes::Eof) = lexer.peek_token().map(|token| token.ty) {
-                    RegexAST::EmptyString
+                    (RegexAST::EmptyString, 0)
                 } else {
-                    parse_regex_expr(lexer, r_bp)?
+                    parse_regex_expr(lexer, r_bp, state)?
                 }
             } else {
-                parse_regex_expr(lexer, r_bp)?
+                parse_regex_expr(lexer, r_bp, state)?
             };
+            depth = depth.max(rhs_depth);
 
-            lhs = RegexAST::Binary(Box::new(lhs), op.get_binary_op(), Box::new(rhs));
+            concatenation.push(std::mem::replace(&mut lhs, rhs));
+            if op.get_binary_op() == BinaryOp::Union {
+                alternatives.push(balanced_tree(std::mem::take(&mut concatenation), true, &concatenate));
+            }
             continue;
         }
 
         break;
     }
 
-    Ok(lhs)
+    concatenation.push(lhs);
+    alternatives.push(balanced_tree(concatenation, true, &concatenate));
+
+    Ok((balanced_tree(alternatives, false, &union), depth))
+}
+
+/// Join the operands of a chain of the same operator into a balanced tree, so long chains like `abc...` or
+/// `a|b|c|...` are only logarithmically deep. The operands are split in half, with the bigger half on the left
+/// unless `right_leaning` is set, e.g. `a(bc)` or `(a|b)|c`.
+fn balanced_tree<T>(mut operands: Vec<T>, right_leaning: bool, join: &impl Fn(T, T) -> T) -> T {
+    if operands.len() == 1 {
+        return operands.pop().unwrap();
+    }
+
+    let middle = if right_leaning {
+        operands.len() / 2
+    } else {
+        operands.len().div_ceil(2)
+    };
+    let rhs = operands.split_off(middle);
+    let lhs = balanced_tree(operands, right_leaning, join);
+
+    join(lhs, balanced_tree(rhs, right_leaning, join))
+}
+
+pub fn concatenate(lhs: RegexAST, rhs: RegexAST) -> RegexAST {
+    RegexAST::Binary(Box::new(lhs), BinaryOp::Concatenation, Box::new(rhs))
+}
+
+fn union(lhs: RegexAST, rhs: RegexAST) -> RegexAST {
+    RegexAST::Binary(Box::new(lhs), BinaryOp::Union, Box::new(rhs))
 }
 
-fn parse_character_class(lexer: &mut Lexer, min_bp: u8) -> Result<CharacterClassType, Error> {
+fn parse_character_class(lexer: &mut Lexer, min_bp: u8, state: &ParserState) -> Result<CharacterClassType, Error> {
     let token = lexer.next_token();
     let mut lhs = match token.ty {
         TokenTypes::Symbol(s) => CharacterClassType::Single(s),
+        TokenTypes::PerlClass(class, negated) => perl_class(class, negated, state),
+        TokenTypes::PosixClass(class, negated) => posix_class(class, negated, state),
+        TokenTypes::UnicodeClass(name_start, name_end, negated) => {
+            unicode_class(lexer, token, name_start, name_end, negated, state)?
+        }
+        TokenTypes::InvalidPosixClass => {
+            let position = token.position();
+            return Err(Error::Syntax(format!(
+                "Invalid character class: \"{}\" at position {} isn't a known POSIX class!",
+                lexer.slice(position.start, position.end),
+                position.start
+            )));
+        }
+        TokenTypes::UnclosedPosixClass => {
+            let position = token.position();
+            return Err(Error::Syntax(format!(
+                "Invalid character class: \"{}\" at position {} isn't closed by \":]\"!",
+                lexer.slice(position.start, position.end),
+                position.start
+            )));
+        }
+        TokenTypes::InvalidEscape(error) => return Err(invalid_escape_error(error, token)),
         TokenTypes::Eof => {
             return Err(Error::Syntax(
                 "Invalid character class: missing closing bracket!".to_string(),
             ))
         }
-        t => panic!("Invalid token {:?}", t),
+        TokenTypes::CloseBracket => {
+            return Err(Error::Syntax(format!(
+                "Invalid character class: the character class ending at position {} is empty!",
+                token.position().start
+            )))
+        }
+        _ => return Err(unexpected_token_error(lexer, token)),
     };
 
+    // The items of the union that is being parsed, like the unions in `parse_regex_expr`
+    let mut items = Vec::new();
+
     while let Some(token) = lexer.peek_token() {
         let op = match token.ty {
             TokenTypes::Union => Operation::CharacterClassBinary(CharacterClassBinaryOp::Union),
             TokenTypes::Dash => Operation::CharacterClassBinary(CharacterClassBinaryOp::Range),
             TokenTypes::CloseBracket => Operation::Unknow(token.ty),
-            TokenTypes::Eof => return Ok(lhs),
-            t => panic!("Error: Unsuported token {:?}", t),
+            TokenTypes::Eof => break,
+            _ => return Err(unexpected_token_error(lexer, token)),
         };
 
         if let Some((lhs_bp, rhs_bp)) = infix_binding_power(op) {
@@ -225,7 +557,7 @@ fn parse_character_class(lexer: &mut Lexer, min_bp: u8) -> Result<CharacterClass
             }
 
             lexer.next_token();
-            let rhs = parse_character_class(lexer, rhs_bp)?;
+            let rhs = parse_character_class(lexer, rhs_bp, state)?;
             let binary_op = op.get_character_class_binary_op();
             if binary_op == CharacterClassBinaryOp::Range {
                 if let (CharacterClassType::Single(lhs), CharacterClassType::Single(rhs)) = (&lhs, &rhs) {
@@ -237,14 +569,147 @@ fn parse_character_class(lexer: &mut Lexer, min_bp: u8) -> Result<CharacterClass
                 }
             }
 
-            lhs = CharacterClassType::Binary(Box::new(lhs), binary_op, Box::new(rhs));
+            if binary_op == CharacterClassBinaryOp::Union {
+                items.push(std::mem::replace(&mut lhs, rhs));
+            } else {
+                lhs = CharacterClassType::Binary(Box::new(lhs), binary_op, Box::new(rhs));
+            }
             continue;
         }
 
         break;
     }
 
-    Ok(lhs)
+    items.push(lhs);
+
+    Ok(balanced_tree(items, false, &|lhs, rhs| {
+        CharacterClassType::Binary(Box::new(lhs), CharacterClassBinaryOp::Union, Box::new(rhs))
+    }))
+}
+
+/// A symbol, or the class of all its case-insensitive equivalents
+fn symbol(symbol: char, state: &ParserState) -> RegexAST {
+    if !state.flags().case_insensitive {
+        return RegexAST::Symbol(symbol);
+    }
+
+    let set = case_fold(&IntervalSet::from_range(symbol, symbol));
+    if set.intervals() == [(symbol as u32, symbol as u32 + 1)] {
+        RegexAST::Symbol(symbol)
+    } else {
+        RegexAST::CharacterClass(CharacterClassType::Set(set))
+    }
+}
+
+fn perl_class(class: PerlClass, negated: bool, state: &ParserState) -> CharacterClassType {
+    class_set(class.intervals(state.config.unicode), negated, state)
+}
+
+fn posix_class(class: PosixClass, negated: bool, state: &ParserState) -> CharacterClassType {
+    class_set(class.intervals(), negated, state)
+}
+
+fn unicode_class(
+    lexer: &Lexer,
+    token: Token,
+    name_start: usize,
+    name_end: usize,
+    negated: bool,
+    state: &ParserState,
+) -> Result<CharacterClassType, Error> {
+    let name = lexer.slice(name_start, name_end);
+    let set = unicode_property(&name).ok_or_else(|| {
+        Error::Syntax(format!(
+            "Invalid unicode class: \"{name}\" at position {} isn't a known general category or script!",
+            token.position().start
+        ))
+    })?;
+
+    Ok(class_set(set, negated, state))
+}
+
+fn class_set(set: IntervalSet, negated: bool, state: &ParserState) -> CharacterClassType {
+    let set = CharacterClassType::Set(if state.flags().case_insensitive {
+        case_fold(&set)
+    } else {
+        set
+    });
+
+    if negated {
+        CharacterClassType::Negated(Box::new(set))
+    } else {
+        set
+    }
+}
+
+fn validate_repetition(min: u32, max: Option<u32>, config: &ParserConfig) -> Result<(), Error> {
+    if let Some(max) = max.filter(|&max| max < min) {
+        return Err(Error::InvalidRange(format!(
+            "Invalid Repetition: \"{min}\" is bigger than \"{max}\"!"
+        )));
+    }
+
+    let biggest_bound = max.unwrap_or(min);
+    if biggest_bound > config.repetition_limit {
+        return Err(Error::Syntax(format!(
+            "Invalid Repetition: \"{biggest_bound}\" exceeds the repetition limit of {}!",
+            config.repetition_limit
+        )));
+    }
+
+    Ok(())
+}
+
+fn invalid_escape_error(error: EscapeError, token: Token) -> Error {
+    let position = token.position().start;
+    Error::Syntax(match error {
+        EscapeError::Dangling => {
+            format!("Invalid escape sequence: \"\\\" at position {position} isn't escaping any character!")
+        }
+        EscapeError::Unknown(symbol) => {
+            format!("Invalid escape sequence: \"\\{symbol}\" at position {position} isn't a known escape sequence!")
+        }
+        EscapeError::MalformedHex => format!(
+            "Invalid escape sequence at position {position}: expected \"\\x\" followed by two hex digits, or \"\\u{{...}}\" with one to six hex digits!"
+        ),
+        EscapeError::InvalidCodePoint(code_point) => format!(
+            "Invalid escape sequence at position {position}: \"{code_point:X}\" isn't a valid unicode scalar value!"
+        ),
+        EscapeError::MalformedUnicodeClass => format!(
+            "Invalid escape sequence at position {position}: expected \"\\p{{...}}\" with the name of a general category or script, or \"\\p\" followed by a one letter category!"
+        ),
+    })
+}
+
+/// A token that can't appear where it was found
+fn unexpected_token_error(lexer: &Lexer, token: Token) -> Error {
+    let position = token.position();
+    Error::Syntax(format!(
+        "Invalid regex: unexpected \"{}\" at position {}!",
+        lexer.slice(position.start, position.end),
+        position.start
+    ))
+}
+
+fn invalid_group_error(error: GroupError, token: Token) -> Error {
+    let position = token.position().start;
+    Error::Syntax(match error {
+        GroupError::InvalidName => format!(
+            "Invalid group name at position {position}: a name must start with a letter or \"_\", followed by letters, digits or \"_\", and end with \">\"!"
+        ),
+        GroupError::UnclosedName => format!(
+            "Invalid group at position {position}: the named group \"(?P\" isn't followed by \"<name>\"!"
+        ),
+        GroupError::Unknown => format!(
+            "Invalid group at position {position}: \"(?\" must be followed by \":\", \"<name>\", \"P<name>\", \"flags)\" or \"flags:\"!"
+        ),
+        GroupError::UnknownFlag(flag) => format!(
+            "Invalid flag at position {position}: \"{flag}\" isn't a known flag, the flags are \"i\", \"m\", \"s\" and \"x\"!"
+        ),
+        GroupError::DuplicateFlag(flag) => {
+            format!("Invalid flag at position {position}: \"{flag}\" appears more than once in the same group!")
+        }
+    })
 }
 
 fn infix_binding_power(op: Operation) -> Option<(u8, u8)> {
@@ -252,7 +717,7 @@ fn infix_binding_power(op: Operation) -> Option<(u8, u8)> {
         Operation::Binary(BinaryOp::Union) | Operation::CharacterClassBinary(CharacterClassBinaryOp::Union) => {
             Some((1, 2))
         }
-        Operation::Binary(BinaryOp::Concatenation) => Some((3, 3)),
+        Operation::Binary(BinaryOp::Concatenation) => Some((3, 4)),
         Operation::CharacterClassBinary(CharacterClassBinaryOp::Range) => Some((6, 5)),
         _ => None,
     }
@@ -0,0 +1,331 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use super::look::{splits_character, Look};
+use super::nfa::{Instruction, Program};
+
+/// The input of a search, read by characters or by bytes depending on the program
+pub trait Haystack {
+    /// The symbol at the byte offset `at`, the code point of a character or a byte, and its length in bytes
+    fn symbol_at(&self, at: usize) -> Option<(u32, usize)>;
+    /// The kind of the character before the byte offset `at`
+    fn behind(&self, at: usize) -> Look;
+    /// The kind of the character after the byte offset `at`
+    fn ahead(&self, at: usize) -> Look;
+    /// Check if the byte offset `at` is in the middle of a character, where no assertion can match
+    fn splits_character(&self, at: usize) -> bool;
+}
+
+impl Haystack for str {
+    fn symbol_at(&self, at: usize) -> Option<(u32, usize)> {
+        self[at..]
+            .chars()
+            .next()
+            .map(|symbol| (u32::from(symbol), symbol.len_utf8()))
+    }
+
+    fn behind(&self, at: usize) -> Look {
+        Look::behind(self, at)
+    }
+
+    fn ahead(&self, at: usize) -> Look {
+        Look::ahead(self, at)
+    }
+
+    fn splits_character(&self, _at: usize) -> bool {
+        // The search only stops at the boundaries of the characters of a `str`
+        false
+    }
+}
+
+impl Haystack for [u8] {
+    fn symbol_at(&self, at: usize) -> Option<(u32, usize)> {
+        self.get(at).map(|&byte| (u32::from(byte), 1))
+    }
+
+    fn behind(&self, at: usize) -> Look {
+        Look::behind_bytes(self, at)
+    }
+
+    fn ahead(&self, at: usize) -> Look {
+        Look::ahead_bytes(self, at)
+    }
+
+    fn splits_character(&self, at: usize) -> bool {
+        splits_character(self, at)
+    }
+}
+
+/// A set of instructions that keeps the insertion order, which is the priority of the threads
+#[derive(Debug)]
+struct SparseSet {
+    dense: Vec<usize>,
+    sparse: Vec<usize>,
+}
+
+impl SparseSet {
+    fn new(capacity: usize) -> Self {
+        Self {
+            dense: Vec::with_capacity(capacity),
+            sparse: vec![0; capacity],
+        }
+    }
+
+    fn contains(&self, value: usize) -> bool {
+        self.sparse[value] < self.dense.len() && self.dense[self.sparse[value]] == value
+    }
+
+    /// Returns `false` if the value was already in the set
+    fn insert(&mut self, value: usize) -> bool {
+        if self.contains(value) {
+            return false;
+        }
+
+        self.sparse[value] = self.dense.len();
+        self.dense.push(value);
+        true
+    }
+
+    fn clear(&mut self) {
+        self.dense.clear();
+    }
+}
+
+/// The threads alive at one position of the input, each thread is identified by its instruction
+#[derive(Debug)]
+struct Threads {
+    set: SparseSet,
+    slots: Vec<Option<usize>>,
+    slots_per_thread: usize,
+}
+
+impl Threads {
+    fn new(program: &Program) -> Self {
+        Self {
+            set: SparseSet::new(program.instructions.len()),
+            slots: vec![None; program.instructions.len() * program.slots],
+            slots_per_thread: program.slots,
+        }
+    }
+
+    fn slots(&self, pc: usize) -> &[Option<usize>] {
+        &self.slots[pc * self.slots_per_thread..(pc + 1) * self.slots_per_thread]
+    }
+
+    fn slots_mut(&mut self, pc: usize) -> &mut [Option<usize>] {
+        &mut self.slots[pc * self.slots_per_thread..(pc + 1) * self.slots_per_thread]
+    }
+}
+
+/// The memory used by a search, it can be reused by the searches of the same program so finding all the matches
+/// of a haystack doesn't allocate it again for every match
+#[derive(Debug)]
+pub struct Cache {
+    current_threads: Threads,
+    next_threads: Threads,
+    stack: Vec<Frame>,
+    slots: Vec<Option<usize>>,
+    /// The slots of the thread of the best match found so far
+    matched_slots: Vec<Option<usize>>,
+}
+
+impl Cache {
+    pub fn new(program: &Program) -> Self {
+        Self {
+            current_threads: Threads::new(program),
+            next_threads: Threads::new(program),
+            stack: Vec::new(),
+            slots: vec![None; program.slots],
+            matched_slots: vec![None; program.slots],
+        }
+    }
+}
+
+/// The caches of the searches of a program. A search takes a cache from the pool and gives it back when it ends,
+/// so the searches don't allocate a cache every time and the ones that run at the same time from different threads
+/// don't wait for each other.
+#[derive(Debug, Default)]
+pub struct CachePool {
+    caches: Mutex<Vec<Cache>>,
+}
+
+impl CachePool {
+    /// A cache of the pool, or a new one for the program when all of them are being used
+    pub fn get<'a>(&'a self, program: &Program) -> PoolGuard<'a> {
+        let cache = self.lock().pop().unwrap_or_else(|| Cache::new(program));
+
+        PoolGuard {
+            pool: self,
+            cache: Some(cache),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Cache>> {
+        // The pool is only changed by pushing and popping whole caches, so it can still be used if a thread
+        // panicked while holding it
+        self.caches.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A cache taken from a [`CachePool`], it is given back to the pool when dropped
+#[derive(Debug)]
+pub struct PoolGuard<'a> {
+    pool: &'a CachePool,
+    /// Always set until the guard is dropped
+    cache: Option<Cache>,
+}
+
+impl Deref for PoolGuard<'_> {
+    type Target = Cache;
+
+    fn deref(&self) -> &Cache {
+        self.cache.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PoolGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Cache {
+        self.cache.as_mut().unwrap()
+    }
+}
+
+impl Drop for PoolGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(cache) = self.cache.take() {
+            self.pool.lock().push(cache);
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Frame {
+    Explore(usize),
+    RestoreSlot(usize, Option<usize>),
+}
+
+/// Simulates a `Program` on all the threads at once, so the search is linear on the size of the input.
+/// Reference: https://swtch.com/~rsc/regexp/regexp2.html
+#[derive(Debug)]
+pub struct PikeVM<'p> {
+    program: &'p Program,
+}
+
+impl<'p> PikeVM<'p> {
+    pub fn new(program: &'p Program) -> Self {
+        Self { program }
+    }
+
+    /// Find the leftmost-first match that starts at or after the byte offset `start`, returning the slots of the
+    /// thread that matched. The haystack is read by bytes if the program is a byte program, otherwise by characters.
+    /// The `cache` must have been created for the same program.
+    pub fn search<H: Haystack + ?Sized>(
+        &self,
+        cache: &mut Cache,
+        haystack: &H,
+        start: usize,
+    ) -> Option<Vec<Option<usize>>> {
+        let Cache {
+            current_threads,
+            next_threads,
+            stack,
+            slots,
+            matched_slots,
+        } = cache;
+        current_threads.set.clear();
+        next_threads.set.clear();
+        let mut matched = false;
+
+        let mut at = start;
+
+        loop {
+            // A match found before means that any match starting from now on wouldn't be the leftmost one
+            if !matched {
+                slots.fill(None);
+                self.add_thread(current_threads, stack, 0, haystack, at, slots);
+            }
+
+            if current_threads.set.dense.is_empty() {
+                break;
+            }
+
+            let symbol = haystack.symbol_at(at);
+            let next_at = at + symbol.map_or(0, |(_, len)| len);
+
+            for i in 0..current_threads.set.dense.len() {
+                let pc = current_threads.set.dense[i];
+
+                match &self.program.instructions[pc] {
+                    Instruction::Match => {
+                        // The threads after this one have lower priority, so they are dropped
+                        matched_slots.copy_from_slice(current_threads.slots(pc));
+                        matched = true;
+                        break;
+                    }
+                    instruction @ Instruction::Ranges(_)
+                        if symbol.is_some_and(|(symbol, _)| instruction.matches(symbol)) =>
+                    {
+                        slots.copy_from_slice(current_threads.slots(pc));
+                        self.add_thread(next_threads, stack, pc + 1, haystack, next_at, slots);
+                    }
+                    _ => {}
+                }
+            }
+
+            if symbol.is_none() {
+                break;
+            }
+
+            std::mem::swap(current_threads, next_threads);
+            next_threads.set.clear();
+            at = next_at;
+        }
+
+        matched.then(|| matched_slots.clone())
+    }
+
+    /// Add the thread at `pc` and follow all the instructions that don't consume input from it, in priority order.
+    /// The `slots` are restored to their original values before returning.
+    fn add_thread<H: Haystack + ?Sized>(
+        &self,
+        threads: &mut Threads,
+        stack: &mut Vec<Frame>,
+        pc: usize,
+        haystack: &H,
+        at: usize,
+        slots: &mut [Option<usize>],
+    ) {
+        stack.push(Frame::Explore(pc));
+
+        while let Some(frame) = stack.pop() {
+            match frame {
+                Frame::RestoreSlot(slot, value) => slots[slot] = value,
+                Frame::Explore(pc) => {
+                    if !threads.set.insert(pc) {
+                        continue;
+                    }
+
+                    match &self.program.instructions[pc] {
+                        Instruction::Ranges(_) | Instruction::Match => threads.slots_mut(pc).copy_from_slice(slots),
+                        Instruction::Jump(target) => stack.push(Frame::Explore(*target)),
+                        Instruction::Split(first, second) => {
+                            stack.push(Frame::Explore(*second));
+                            stack.push(Frame::Explore(*first));
+                        }
+                        Instruction::Assertion(assertion) => {
+                            if !haystack.splits_character(at)
+                                && assertion.is_satisfied(haystack.behind(at), haystack.ahead(at))
+                            {
+                                stack.push(Frame::Explore(pc + 1));
+                            }
+                        }
+                        Instruction::Save(slot) => {
+                            stack.push(Frame::RestoreSlot(*slot, slots[*slot]));
+                            stack.push(Frame::Explore(pc + 1));
+                            slots[*slot] = Some(at);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
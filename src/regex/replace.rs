@@ -0,0 +1,96 @@
+use super::Captures;
+
+/// Something that can build the replacement of a match, used by [`Regex::replace`](super::Regex::replace) and
+/// [`Regex::replace_all`](super::Regex::replace_all).
+///
+/// - `&str` and `String` are templates, where `$n` and `${n}` are replaced by the capture group `n`, `$name` and
+///   `${name}` by the group called `name`, and `$$` is a literal `$`. A group that doesn't exist or didn't
+///   participate in the match is replaced by the empty string.
+/// - A closure `FnMut(&Captures) -> String` computes the replacement of every match.
+/// - [`NoExpand`] is used as is, without looking for any `$`.
+pub trait Replacer {
+    /// Append the replacement of the match in `captures` to `dst`
+    fn replace_append(&mut self, captures: &Captures, dst: &mut String);
+}
+
+/// A replacement that is used literally, e.g. `NoExpand("$1")` is replaced by `$1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoExpand<'s>(pub &'s str);
+
+impl Replacer for &str {
+    fn replace_append(&mut self, captures: &Captures, dst: &mut String) {
+        expand(captures, self, dst);
+    }
+}
+
+impl Replacer for String {
+    fn replace_append(&mut self, captures: &Captures, dst: &mut String) {
+        expand(captures, self, dst);
+    }
+}
+
+impl Replacer for &String {
+    fn replace_append(&mut self, captures: &Captures, dst: &mut String) {
+        expand(captures, self, dst);
+    }
+}
+
+impl<'s> Replacer for NoExpand<'s> {
+    fn replace_append(&mut self, _: &Captures, dst: &mut String) {
+        dst.push_str(self.0);
+    }
+}
+
+impl<F, T> Replacer for F
+where
+    F: FnMut(&Captures) -> T,
+    T: AsRef<str>,
+{
+    fn replace_append(&mut self, captures: &Captures, dst: &mut String) {
+        dst.push_str(self(captures).as_ref());
+    }
+}
+
+/// Append `template` to `dst` replacing the references to capture groups, see [`Replacer`] for the syntax
+pub fn expand(captures: &Captures, mut template: &str, dst: &mut String) {
+    while let Some(position) = template.find('$') {
+        dst.push_str(&template[..position]);
+        template = &template[position + 1..];
+
+        if let Some(rest) = template.strip_prefix('$') {
+            dst.push('$');
+            template = rest;
+            continue;
+        }
+
+        let (reference, rest) = match template.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => ("", template),
+            },
+            None => {
+                let end = template
+                    .find(|symbol: char| !(symbol.is_alphanumeric() || symbol == '_'))
+                    .unwrap_or(template.len());
+                template.split_at(end)
+            }
+        };
+
+        // A "$" that isn't followed by a reference is a literal
+        if reference.is_empty() {
+            dst.push('$');
+            continue;
+        }
+
+        let group = match reference.parse::<usize>() {
+            Ok(index) => captures.get(index),
+            Err(_) => captures.name(reference),
+        };
+        if let Some(group) = group {
+            dst.push_str(group.as_str());
+        }
+        template = rest;
+    }
+
+    dst.push_str(template);
+}
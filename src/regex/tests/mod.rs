@@ -1,3 +1,8 @@
 mod test_lexer;
 mod test_parser;
 mod test_automata;
+mod test_dense;
+mod test_interval;
+mod test_nfa;
+mod test_lazy;
+mod test_utf8;
@@ -1,19 +1,24 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 
-use crate::regex::{automata::*, parser::parse_regex};
+use crate::regex::{
+    automata::*,
+    debug::AutomataDebug,
+    interval::CODE_POINT_END,
+    parser::{parse_regex, parse_regex_with_config, ParserConfig},
+};
 
 fn create_automata() -> Automata {
     let mut auto = Automata::new(0);
     auto.add_transition(0, TransitionType::Epsilon, 1);
     auto.add_transition(0, TransitionType::Epsilon, 5);
     auto.add_transition(0, TransitionType::Epsilon, 8);
-    auto.add_transition(1, TransitionType::Symbol('a'), 2);
-    auto.add_transition(2, TransitionType::Symbol('b'), 3);
-    auto.add_transition(3, TransitionType::Symbol('c'), 4);
-    auto.add_transition(5, TransitionType::Symbol('b'), 6);
-    auto.add_transition(6, TransitionType::Symbol('c'), 7);
-    auto.add_transition(8, TransitionType::Symbol('a'), 9);
-    auto.add_transition(9, TransitionType::Symbol('d'), 10);
+    auto.add_transition(1, TransitionType::symbol('a'), 2);
+    auto.add_transition(2, TransitionType::symbol('b'), 3);
+    auto.add_transition(3, TransitionType::symbol('c'), 4);
+    auto.add_transition(5, TransitionType::symbol('b'), 6);
+    auto.add_transition(6, TransitionType::symbol('c'), 7);
+    auto.add_transition(8, TransitionType::symbol('a'), 9);
+    auto.add_transition(9, TransitionType::symbol('d'), 10);
     auto.add_final_state(4);
     auto.add_final_state(7);
     auto.add_final_state(10);
@@ -28,15 +33,15 @@ fn test_reverse_automata() {
 
     expected_automata.add_final_state(0);
     expected_automata.add_transition(1, TransitionType::Epsilon, 0);
-    expected_automata.add_transition(2, TransitionType::Symbol('a'), 1);
-    expected_automata.add_transition(3, TransitionType::Symbol('b'), 2);
-    expected_automata.add_transition(4, TransitionType::Symbol('c'), 3);
+    expected_automata.add_transition(2, TransitionType::symbol('a'), 1);
+    expected_automata.add_transition(3, TransitionType::symbol('b'), 2);
+    expected_automata.add_transition(4, TransitionType::symbol('c'), 3);
     expected_automata.add_transition(5, TransitionType::Epsilon, 0);
-    expected_automata.add_transition(6, TransitionType::Symbol('b'), 5);
+    expected_automata.add_transition(6, TransitionType::symbol('b'), 5);
     expected_automata.add_transition(8, TransitionType::Epsilon, 0);
-    expected_automata.add_transition(7, TransitionType::Symbol('c'), 6);
-    expected_automata.add_transition(9, TransitionType::Symbol('a'), 8);
-    expected_automata.add_transition(10, TransitionType::Symbol('d'), 9);
+    expected_automata.add_transition(7, TransitionType::symbol('c'), 6);
+    expected_automata.add_transition(9, TransitionType::symbol('a'), 8);
+    expected_automata.add_transition(10, TransitionType::symbol('d'), 9);
     expected_automata.add_transition(11, TransitionType::Epsilon, 4);
     expected_automata.add_transition(11, TransitionType::Epsilon, 7);
     expected_automata.add_transition(11, TransitionType::Epsilon, 10);
@@ -46,118 +51,118 @@ fn test_reverse_automata() {
 
 #[test]
 fn test_subset_reverse_automata() {
-    let subset_reversed_automata = subset(reverse(create_automata()));
+    let subset_reversed_automata = subset(reverse(create_automata()), usize::MAX).unwrap();
     let mut expected_automata = Automata::new(0);
     expected_automata.add_final_state(4);
     expected_automata.add_final_state(5);
     expected_automata.add_final_state(6);
 
-    expected_automata.add_transition(0, TransitionType::Symbol('a'), 1);
-    expected_automata.add_transition(0, TransitionType::Symbol('b'), 1);
-    expected_automata.add_transition(0, TransitionType::Symbol('c'), 2);
-    expected_automata.add_transition(0, TransitionType::Symbol('d'), 3);
-    expected_automata.add_transition(1, TransitionType::Symbol('a'), 1);
-    expected_automata.add_transition(1, TransitionType::Symbol('b'), 1);
-    expected_automata.add_transition(1, TransitionType::Symbol('c'), 1);
-    expected_automata.add_transition(1, TransitionType::Symbol('d'), 1);
-    expected_automata.add_transition(2, TransitionType::Symbol('a'), 1);
-    expected_automata.add_transition(2, TransitionType::Symbol('b'), 5);
-    expected_automata.add_transition(2, TransitionType::Symbol('c'), 1);
-    expected_automata.add_transition(2, TransitionType::Symbol('d'), 1);
-    expected_automata.add_transition(3, TransitionType::Symbol('a'), 4);
-    expected_automata.add_transition(3, TransitionType::Symbol('b'), 1);
-    expected_automata.add_transition(3, TransitionType::Symbol('c'), 1);
-    expected_automata.add_transition(3, TransitionType::Symbol('d'), 1);
-    expected_automata.add_transition(4, TransitionType::Symbol('a'), 1);
-    expected_automata.add_transition(4, TransitionType::Symbol('b'), 1);
-    expected_automata.add_transition(4, TransitionType::Symbol('c'), 1);
-    expected_automata.add_transition(4, TransitionType::Symbol('d'), 1);
-    expected_automata.add_transition(5, TransitionType::Symbol('a'), 6);
-    expected_automata.add_transition(5, TransitionType::Symbol('b'), 1);
-    expected_automata.add_transition(5, TransitionType::Symbol('c'), 1);
-    expected_automata.add_transition(5, TransitionType::Symbol('d'), 1);
-    expected_automata.add_transition(6, TransitionType::Symbol('a'), 1);
-    expected_automata.add_transition(6, TransitionType::Symbol('b'), 1);
-    expected_automata.add_transition(6, TransitionType::Symbol('c'), 1);
-    expected_automata.add_transition(6, TransitionType::Symbol('d'), 1);
+    expected_automata.add_transition(0, TransitionType::symbol('a'), 1);
+    expected_automata.add_transition(0, TransitionType::symbol('b'), 1);
+    expected_automata.add_transition(0, TransitionType::symbol('c'), 2);
+    expected_automata.add_transition(0, TransitionType::symbol('d'), 3);
+    expected_automata.add_transition(1, TransitionType::symbol('a'), 1);
+    expected_automata.add_transition(1, TransitionType::symbol('b'), 1);
+    expected_automata.add_transition(1, TransitionType::symbol('c'), 1);
+    expected_automata.add_transition(1, TransitionType::symbol('d'), 1);
+    expected_automata.add_transition(2, TransitionType::symbol('a'), 1);
+    expected_automata.add_transition(2, TransitionType::symbol('b'), 5);
+    expected_automata.add_transition(2, TransitionType::symbol('c'), 1);
+    expected_automata.add_transition(2, TransitionType::symbol('d'), 1);
+    expected_automata.add_transition(3, TransitionType::symbol('a'), 4);
+    expected_automata.add_transition(3, TransitionType::symbol('b'), 1);
+    expected_automata.add_transition(3, TransitionType::symbol('c'), 1);
+    expected_automata.add_transition(3, TransitionType::symbol('d'), 1);
+    expected_automata.add_transition(4, TransitionType::symbol('a'), 1);
+    expected_automata.add_transition(4, TransitionType::symbol('b'), 1);
+    expected_automata.add_transition(4, TransitionType::symbol('c'), 1);
+    expected_automata.add_transition(4, TransitionType::symbol('d'), 1);
+    expected_automata.add_transition(5, TransitionType::symbol('a'), 6);
+    expected_automata.add_transition(5, TransitionType::symbol('b'), 1);
+    expected_automata.add_transition(5, TransitionType::symbol('c'), 1);
+    expected_automata.add_transition(5, TransitionType::symbol('d'), 1);
+    expected_automata.add_transition(6, TransitionType::symbol('a'), 1);
+    expected_automata.add_transition(6, TransitionType::symbol('b'), 1);
+    expected_automata.add_transition(6, TransitionType::symbol('c'), 1);
+    expected_automata.add_transition(6, TransitionType::symbol('d'), 1);
 
     assert_eq!(subset_reversed_automata, expected_automata)
 }
 
 #[test]
 fn test_reachable_subset_reverse_automata() {
-    let subset_reversed_automata = reachable(subset(reverse(create_automata())));
+    let subset_reversed_automata = reachable(subset(reverse(create_automata()), usize::MAX).unwrap());
     let mut expected_automata = Automata::new(0);
     expected_automata.add_final_state(4);
     expected_automata.add_final_state(5);
     expected_automata.add_final_state(6);
 
-    expected_automata.add_transition(0, TransitionType::Symbol('a'), 1);
-    expected_automata.add_transition(0, TransitionType::Symbol('b'), 1);
-    expected_automata.add_transition(0, TransitionType::Symbol('c'), 2);
-    expected_automata.add_transition(0, TransitionType::Symbol('d'), 3);
-    expected_automata.add_transition(1, TransitionType::Symbol('a'), 1);
-    expected_automata.add_transition(1, TransitionType::Symbol('b'), 1);
-    expected_automata.add_transition(1, TransitionType::Symbol('c'), 1);
-    expected_automata.add_transition(1, TransitionType::Symbol('d'), 1);
-    expected_automata.add_transition(2, TransitionType::Symbol('a'), 1);
-    expected_automata.add_transition(2, TransitionType::Symbol('b'), 5);
-    expected_automata.add_transition(2, TransitionType::Symbol('c'), 1);
-    expected_automata.add_transition(2, TransitionType::Symbol('d'), 1);
-    expected_automata.add_transition(3, TransitionType::Symbol('a'), 4);
-    expected_automata.add_transition(3, TransitionType::Symbol('b'), 1);
-    expected_automata.add_transition(3, TransitionType::Symbol('c'), 1);
-    expected_automata.add_transition(3, TransitionType::Symbol('d'), 1);
-    expected_automata.add_transition(4, TransitionType::Symbol('a'), 1);
-    expected_automata.add_transition(4, TransitionType::Symbol('b'), 1);
-    expected_automata.add_transition(4, TransitionType::Symbol('c'), 1);
-    expected_automata.add_transition(4, TransitionType::Symbol('d'), 1);
-    expected_automata.add_transition(5, TransitionType::Symbol('a'), 6);
-    expected_automata.add_transition(5, TransitionType::Symbol('b'), 1);
-    expected_automata.add_transition(5, TransitionType::Symbol('c'), 1);
-    expected_automata.add_transition(5, TransitionType::Symbol('d'), 1);
-    expected_automata.add_transition(6, TransitionType::Symbol('a'), 1);
-    expected_automata.add_transition(6, TransitionType::Symbol('b'), 1);
-    expected_automata.add_transition(6, TransitionType::Symbol('c'), 1);
-    expected_automata.add_transition(6, TransitionType::Symbol('d'), 1);
+    expected_automata.add_transition(0, TransitionType::symbol('a'), 1);
+    expected_automata.add_transition(0, TransitionType::symbol('b'), 1);
+    expected_automata.add_transition(0, TransitionType::symbol('c'), 2);
+    expected_automata.add_transition(0, TransitionType::symbol('d'), 3);
+    expected_automata.add_transition(1, TransitionType::symbol('a'), 1);
+    expected_automata.add_transition(1, TransitionType::symbol('b'), 1);
+    expected_automata.add_transition(1, TransitionType::symbol('c'), 1);
+    expected_automata.add_transition(1, TransitionType::symbol('d'), 1);
+    expected_automata.add_transition(2, TransitionType::symbol('a'), 1);
+    expected_automata.add_transition(2, TransitionType::symbol('b'), 5);
+    expected_automata.add_transition(2, TransitionType::symbol('c'), 1);
+    expected_automata.add_transition(2, TransitionType::symbol('d'), 1);
+    expected_automata.add_transition(3, TransitionType::symbol('a'), 4);
+    expected_automata.add_transition(3, TransitionType::symbol('b'), 1);
+    expected_automata.add_transition(3, TransitionType::symbol('c'), 1);
+    expected_automata.add_transition(3, TransitionType::symbol('d'), 1);
+    expected_automata.add_transition(4, TransitionType::symbol('a'), 1);
+    expected_automata.add_transition(4, TransitionType::symbol('b'), 1);
+    expected_automata.add_transition(4, TransitionType::symbol('c'), 1);
+    expected_automata.add_transition(4, TransitionType::symbol('d'), 1);
+    expected_automata.add_transition(5, TransitionType::symbol('a'), 6);
+    expected_automata.add_transition(5, TransitionType::symbol('b'), 1);
+    expected_automata.add_transition(5, TransitionType::symbol('c'), 1);
+    expected_automata.add_transition(5, TransitionType::symbol('d'), 1);
+    expected_automata.add_transition(6, TransitionType::symbol('a'), 1);
+    expected_automata.add_transition(6, TransitionType::symbol('b'), 1);
+    expected_automata.add_transition(6, TransitionType::symbol('c'), 1);
+    expected_automata.add_transition(6, TransitionType::symbol('d'), 1);
 
     assert_eq!(subset_reversed_automata, expected_automata)
 }
 
 #[test]
 fn test_reverse_reachable_subset_reverse_automata() {
-    let reverse_subset_reversed_automata = reverse(reachable(subset(reverse(create_automata()))));
+    let reverse_subset_reversed_automata = reverse(reachable(subset(reverse(create_automata()), usize::MAX).unwrap()));
     let mut expected_automata = Automata::new(7);
     expected_automata.add_final_state(0);
 
-    expected_automata.add_transition(1, TransitionType::Symbol('a'), 0);
-    expected_automata.add_transition(1, TransitionType::Symbol('a'), 1);
-    expected_automata.add_transition(1, TransitionType::Symbol('a'), 2);
-    expected_automata.add_transition(1, TransitionType::Symbol('a'), 4);
-    expected_automata.add_transition(1, TransitionType::Symbol('a'), 6);
-    expected_automata.add_transition(1, TransitionType::Symbol('b'), 0);
-    expected_automata.add_transition(1, TransitionType::Symbol('b'), 1);
-    expected_automata.add_transition(1, TransitionType::Symbol('b'), 3);
-    expected_automata.add_transition(1, TransitionType::Symbol('b'), 4);
-    expected_automata.add_transition(1, TransitionType::Symbol('b'), 5);
-    expected_automata.add_transition(1, TransitionType::Symbol('b'), 6);
-    expected_automata.add_transition(1, TransitionType::Symbol('c'), 1);
-    expected_automata.add_transition(1, TransitionType::Symbol('c'), 2);
-    expected_automata.add_transition(1, TransitionType::Symbol('c'), 3);
-    expected_automata.add_transition(1, TransitionType::Symbol('c'), 4);
-    expected_automata.add_transition(1, TransitionType::Symbol('c'), 5);
-    expected_automata.add_transition(1, TransitionType::Symbol('c'), 6);
-    expected_automata.add_transition(1, TransitionType::Symbol('d'), 1);
-    expected_automata.add_transition(1, TransitionType::Symbol('d'), 2);
-    expected_automata.add_transition(1, TransitionType::Symbol('d'), 3);
-    expected_automata.add_transition(1, TransitionType::Symbol('d'), 4);
-    expected_automata.add_transition(1, TransitionType::Symbol('d'), 5);
-    expected_automata.add_transition(1, TransitionType::Symbol('d'), 6);
-    expected_automata.add_transition(2, TransitionType::Symbol('c'), 0);
-    expected_automata.add_transition(3, TransitionType::Symbol('d'), 0);
-    expected_automata.add_transition(4, TransitionType::Symbol('a'), 3);
-    expected_automata.add_transition(5, TransitionType::Symbol('b'), 2);
-    expected_automata.add_transition(6, TransitionType::Symbol('a'), 5);
+    expected_automata.add_transition(1, TransitionType::symbol('a'), 0);
+    expected_automata.add_transition(1, TransitionType::symbol('a'), 1);
+    expected_automata.add_transition(1, TransitionType::symbol('a'), 2);
+    expected_automata.add_transition(1, TransitionType::symbol('a'), 4);
+    expected_automata.add_transition(1, TransitionType::symbol('a'), 6);
+    expected_automata.add_transition(1, TransitionType::symbol('b'), 0);
+    expected_automata.add_transition(1, TransitionType::symbol('b'), 1);
+    expected_automata.add_transition(1, TransitionType::symbol('b'), 3);
+    expected_automata.add_transition(1, TransitionType::symbol('b'), 4);
+    expected_automata.add_transition(1, TransitionType::symbol('b'), 5);
+    expected_automata.add_transition(1, TransitionType::symbol('b'), 6);
+    expected_automata.add_transition(1, TransitionType::symbol('c'), 1);
+    expected_automata.add_transition(1, TransitionType::symbol('c'), 2);
+    expected_automata.add_transition(1, TransitionType::symbol('c'), 3);
+    expected_automata.add_transition(1, TransitionType::symbol('c'), 4);
+    expected_automata.add_transition(1, TransitionType::symbol('c'), 5);
+    expected_automata.add_transition(1, TransitionType::symbol('c'), 6);
+    expected_automata.add_transition(1, TransitionType::symbol('d'), 1);
+    expected_automata.add_transition(1, TransitionType::symbol('d'), 2);
+    expected_automata.add_transition(1, TransitionType::symbol('d'), 3);
+    expected_automata.add_transition(1, TransitionType::symbol('d'), 4);
+    expected_automata.add_transition(1, TransitionType::symbol('d'), 5);
+    expected_automata.add_transition(1, TransitionType::symbol('d'), 6);
+    expected_automata.add_transition(2, TransitionType::symbol('c'), 0);
+    expected_automata.add_transition(3, TransitionType::symbol('d'), 0);
+    expected_automata.add_transition(4, TransitionType::symbol('a'), 3);
+    expected_automata.add_transition(5, TransitionType::symbol('b'), 2);
+    expected_automata.add_transition(6, TransitionType::symbol('a'), 5);
     expected_automata.add_transition(7, TransitionType::Epsilon, 4);
     expected_automata.add_transition(7, TransitionType::Epsilon, 5);
     expected_automata.add_transition(7, TransitionType::Epsilon, 6);
@@ -167,74 +172,79 @@ fn test_reverse_reachable_subset_reverse_automata() {
 
 #[test]
 fn test_subset_reverse_subset_reversed_automata() {
-    let subset_reverse_subset_reversed_automata = subset(reverse(reachable(subset(reverse(create_automata())))));
+    let subset_reverse_subset_reversed_automata = subset(
+        reverse(reachable(subset(reverse(create_automata()), usize::MAX).unwrap())),
+        usize::MAX,
+    )
+    .unwrap();
     let mut expected_automata = Automata::new(0);
     expected_automata.add_final_state(4);
 
-    expected_automata.add_transition(0, TransitionType::Symbol('a'), 1);
-    expected_automata.add_transition(0, TransitionType::Symbol('b'), 2);
-    expected_automata.add_transition(0, TransitionType::Symbol('c'), 3);
-    expected_automata.add_transition(0, TransitionType::Symbol('d'), 3);
-    expected_automata.add_transition(1, TransitionType::Symbol('a'), 3);
-    expected_automata.add_transition(1, TransitionType::Symbol('b'), 2);
-    expected_automata.add_transition(1, TransitionType::Symbol('c'), 3);
-    expected_automata.add_transition(1, TransitionType::Symbol('d'), 4);
-    expected_automata.add_transition(2, TransitionType::Symbol('a'), 3);
-    expected_automata.add_transition(2, TransitionType::Symbol('b'), 3);
-    expected_automata.add_transition(2, TransitionType::Symbol('c'), 4);
-    expected_automata.add_transition(2, TransitionType::Symbol('d'), 3);
-    expected_automata.add_transition(3, TransitionType::Symbol('a'), 3);
-    expected_automata.add_transition(3, TransitionType::Symbol('b'), 3);
-    expected_automata.add_transition(3, TransitionType::Symbol('c'), 3);
-    expected_automata.add_transition(3, TransitionType::Symbol('d'), 3);
-    expected_automata.add_transition(4, TransitionType::Symbol('a'), 3);
-    expected_automata.add_transition(4, TransitionType::Symbol('b'), 3);
-    expected_automata.add_transition(4, TransitionType::Symbol('c'), 3);
-    expected_automata.add_transition(4, TransitionType::Symbol('d'), 3);
+    expected_automata.add_transition(0, TransitionType::symbol('a'), 1);
+    expected_automata.add_transition(0, TransitionType::symbol('b'), 2);
+    expected_automata.add_transition(0, TransitionType::symbol('c'), 3);
+    expected_automata.add_transition(0, TransitionType::symbol('d'), 3);
+    expected_automata.add_transition(1, TransitionType::symbol('a'), 3);
+    expected_automata.add_transition(1, TransitionType::symbol('b'), 2);
+    expected_automata.add_transition(1, TransitionType::symbol('c'), 3);
+    expected_automata.add_transition(1, TransitionType::symbol('d'), 4);
+    expected_automata.add_transition(2, TransitionType::symbol('a'), 3);
+    expected_automata.add_transition(2, TransitionType::symbol('b'), 3);
+    expected_automata.add_transition(2, TransitionType::symbol('c'), 4);
+    expected_automata.add_transition(2, TransitionType::symbol('d'), 3);
+    expected_automata.add_transition(3, TransitionType::symbol('a'), 3);
+    expected_automata.add_transition(3, TransitionType::symbol('b'), 3);
+    expected_automata.add_transition(3, TransitionType::symbol('c'), 3);
+    expected_automata.add_transition(3, TransitionType::symbol('d'), 3);
+    expected_automata.add_transition(4, TransitionType::symbol('a'), 3);
+    expected_automata.add_transition(4, TransitionType::symbol('b'), 3);
+    expected_automata.add_transition(4, TransitionType::symbol('c'), 3);
+    expected_automata.add_transition(4, TransitionType::symbol('d'), 3);
 
     assert_eq!(subset_reverse_subset_reversed_automata, expected_automata)
 }
 
 #[test]
 fn test_reachable_subset_reverse_subset_reversed_automata() {
-    let reachable_subset_reverse_subset_reversed_automata =
-        reachable(subset(reverse(reachable(subset(reverse(create_automata()))))));
+    let reachable_subset_reverse_subset_reversed_automata = reachable(
+        subset(
+            reverse(reachable(subset(reverse(create_automata()), usize::MAX).unwrap())),
+            usize::MAX,
+        )
+        .unwrap(),
+    );
     let mut expected_automata = Automata::new(0);
     expected_automata.add_final_state(4);
 
-    expected_automata.add_transition(0, TransitionType::Symbol('a'), 1);
-    expected_automata.add_transition(0, TransitionType::Symbol('b'), 2);
-    expected_automata.add_transition(0, TransitionType::Symbol('c'), 3);
-    expected_automata.add_transition(0, TransitionType::Symbol('d'), 3);
-    expected_automata.add_transition(1, TransitionType::Symbol('a'), 3);
-    expected_automata.add_transition(1, TransitionType::Symbol('b'), 2);
-    expected_automata.add_transition(1, TransitionType::Symbol('c'), 3);
-    expected_automata.add_transition(1, TransitionType::Symbol('d'), 4);
-    expected_automata.add_transition(2, TransitionType::Symbol('a'), 3);
-    expected_automata.add_transition(2, TransitionType::Symbol('b'), 3);
-    expected_automata.add_transition(2, TransitionType::Symbol('c'), 4);
-    expected_automata.add_transition(2, TransitionType::Symbol('d'), 3);
-    expected_automata.add_transition(3, TransitionType::Symbol('a'), 3);
-    expected_automata.add_transition(3, TransitionType::Symbol('b'), 3);
-    expected_automata.add_transition(3, TransitionType::Symbol('c'), 3);
-    expected_automata.add_transition(3, TransitionType::Symbol('d'), 3);
-    expected_automata.add_transition(4, TransitionType::Symbol('a'), 3);
-    expected_automata.add_transition(4, TransitionType::Symbol('b'), 3);
-    expected_automata.add_transition(4, TransitionType::Symbol('c'), 3);
-    expected_automata.add_transition(4, TransitionType::Symbol('d'), 3);
+    expected_automata.add_transition(0, TransitionType::symbol('a'), 1);
+    expected_automata.add_transition(0, TransitionType::symbol('b'), 2);
+    expected_automata.add_transition(0, TransitionType::symbol('c'), 3);
+    expected_automata.add_transition(0, TransitionType::symbol('d'), 3);
+    expected_automata.add_transition(1, TransitionType::symbol('a'), 3);
+    expected_automata.add_transition(1, TransitionType::symbol('b'), 2);
+    expected_automata.add_transition(1, TransitionType::symbol('c'), 3);
+    expected_automata.add_transition(1, TransitionType::symbol('d'), 4);
+    expected_automata.add_transition(2, TransitionType::symbol('a'), 3);
+    expected_automata.add_transition(2, TransitionType::symbol('b'), 3);
+    expected_automata.add_transition(2, TransitionType::symbol('c'), 4);
+    expected_automata.add_transition(2, TransitionType::symbol('d'), 3);
+    expected_automata.add_transition(3, TransitionType::symbol('a'), 3);
+    expected_automata.add_transition(3, TransitionType::symbol('b'), 3);
+    expected_automata.add_transition(3, TransitionType::symbol('c'), 3);
+    expected_automata.add_transition(3, TransitionType::symbol('d'), 3);
+    expected_automata.add_transition(4, TransitionType::symbol('a'), 3);
+    expected_automata.add_transition(4, TransitionType::symbol('b'), 3);
+    expected_automata.add_transition(4, TransitionType::symbol('c'), 3);
+    expected_automata.add_transition(4, TransitionType::symbol('d'), 3);
 
     assert_eq!(reachable_subset_reverse_subset_reversed_automata, expected_automata)
 }
 
 #[test]
 fn create_automata_from_regex_character_class_range() {
-    let automata = build_automata_from_ast(parse_regex("[a-e]").unwrap(), &mut 0);
+    let automata = build_automata_from_ast(parse_regex("[a-e]").unwrap(), &mut 0, usize::MAX).unwrap();
     let mut expected_automata = Automata::new(0);
-    expected_automata.add_transition(0, TransitionType::Symbol('a'), 1);
-    expected_automata.add_transition(0, TransitionType::Symbol('b'), 1);
-    expected_automata.add_transition(0, TransitionType::Symbol('c'), 1);
-    expected_automata.add_transition(0, TransitionType::Symbol('d'), 1);
-    expected_automata.add_transition(0, TransitionType::Symbol('e'), 1);
+    expected_automata.add_transition(0, TransitionType::range('a', 'e'), 1);
     expected_automata.add_final_state(1);
 
     assert_eq!(automata, expected_automata)
@@ -242,15 +252,11 @@ fn create_automata_from_regex_character_class_range() {
 
 #[test]
 fn create_automata_from_regex_character_class_range2() {
-    let automata = build_automata_from_ast(parse_regex("1[a-e]").unwrap(), &mut 0);
+    let automata = build_automata_from_ast(parse_regex("1[a-e]").unwrap(), &mut 0, usize::MAX).unwrap();
     let mut expected_automata = Automata::new(0);
-    expected_automata.add_transition(0, TransitionType::Symbol('1'), 1);
+    expected_automata.add_transition(0, TransitionType::symbol('1'), 1);
     expected_automata.add_transition(1, TransitionType::Epsilon, 2);
-    expected_automata.add_transition(2, TransitionType::Symbol('a'), 3);
-    expected_automata.add_transition(2, TransitionType::Symbol('b'), 3);
-    expected_automata.add_transition(2, TransitionType::Symbol('c'), 3);
-    expected_automata.add_transition(2, TransitionType::Symbol('d'), 3);
-    expected_automata.add_transition(2, TransitionType::Symbol('e'), 3);
+    expected_automata.add_transition(2, TransitionType::range('a', 'e'), 3);
     expected_automata.add_final_state(3);
 
     assert_eq!(automata, expected_automata)
@@ -263,10 +269,281 @@ fn test_eclosure() {
     automata.add_transition(0, TransitionType::Epsilon, 2);
     automata.add_transition(1, TransitionType::Epsilon, 3);
     automata.add_transition(2, TransitionType::Epsilon, 4);
-    automata.add_transition(4, TransitionType::Symbol('a'), 5);
-    automata.add_transition(5, TransitionType::Symbol('b'), 6);
+    automata.add_transition(4, TransitionType::symbol('a'), 5);
+    automata.add_transition(5, TransitionType::symbol('b'), 6);
     automata.add_final_state(5);
     automata.add_final_state(6);
 
     assert_eq!(automata.eclosure(BTreeSet::from([0])), BTreeSet::from([0, 1, 2, 3, 4]));
 }
+
+#[test]
+fn test_subset_negated_character_class() {
+    let automata = reachable(
+        subset(
+            build_automata_from_ast(parse_regex("[^a]b").unwrap(), &mut 0, usize::MAX).unwrap(),
+            usize::MAX,
+        )
+        .unwrap(),
+    );
+    let mut expected_automata = Automata::new(0);
+    expected_automata.add_final_state(3);
+
+    for state in 0..=3 {
+        let (not_b_state, b_state) = match state {
+            0 => (1, 1),
+            1 => (2, 3),
+            _ => (2, 2),
+        };
+        expected_automata.add_transition(state, TransitionType::range('\0', '`'), not_b_state);
+        expected_automata.add_transition(state, TransitionType::symbol('b'), b_state);
+        expected_automata.add_transition(state, TransitionType::range('c', char::MAX), not_b_state);
+    }
+
+    assert_eq!(automata, expected_automata)
+}
+
+#[test]
+fn test_subset_splits_overlapping_ranges() {
+    let automata = reachable(
+        subset(
+            build_automata_from_ast(parse_regex("[a-z]|[d-f]x").unwrap(), &mut 0, usize::MAX).unwrap(),
+            usize::MAX,
+        )
+        .unwrap(),
+    );
+    let mut expected_automata = Automata::new(0);
+    expected_automata.add_final_state(1);
+    expected_automata.add_final_state(2);
+    expected_automata.add_final_state(4);
+
+    expected_automata.add_transition(0, TransitionType::range('a', 'c'), 1);
+    expected_automata.add_transition(0, TransitionType::range('d', 'f'), 2);
+    expected_automata.add_transition(0, TransitionType::range('g', 'w'), 1);
+    expected_automata.add_transition(0, TransitionType::symbol('x'), 1);
+    expected_automata.add_transition(0, TransitionType::range('y', 'z'), 1);
+    for state in 1..=4 {
+        let x_state = if state == 2 { 4 } else { 3 };
+        expected_automata.add_transition(state, TransitionType::range('a', 'c'), 3);
+        expected_automata.add_transition(state, TransitionType::range('d', 'f'), 3);
+        expected_automata.add_transition(state, TransitionType::range('g', 'w'), 3);
+        expected_automata.add_transition(state, TransitionType::symbol('x'), x_state);
+        expected_automata.add_transition(state, TransitionType::range('y', 'z'), 3);
+    }
+
+    assert_eq!(automata, expected_automata)
+}
+
+#[test]
+fn test_huge_character_class_is_a_single_transition() {
+    let automata = build_automata_from_ast(parse_regex("[\\u{0}-\\u{10FFFF}]").unwrap(), &mut 0, usize::MAX).unwrap();
+    let mut expected_automata = Automata::new(0);
+    expected_automata.add_transition(0, TransitionType::range('\0', char::MAX), 1);
+    expected_automata.add_final_state(1);
+
+    assert_eq!(automata, expected_automata)
+}
+
+#[test]
+fn test_subset_merges_any_character_with_overlapping_symbol() {
+    let automata = reachable(
+        subset(
+            build_automata_from_ast(parse_regex("(?s)a.|ab").unwrap(), &mut 0, usize::MAX).unwrap(),
+            usize::MAX,
+        )
+        .unwrap(),
+    );
+
+    // From the start state "a" goes to a state that has both branches, then "b" is accepted by both of them
+    let mut expected_automata = Automata::new(0);
+    expected_automata.add_final_state(3);
+    expected_automata.add_final_state(4);
+
+    expected_automata.add_transition(0, TransitionType::range('\0', '`'), 1);
+    expected_automata.add_transition(0, TransitionType::symbol('a'), 2);
+    expected_automata.add_transition(0, TransitionType::symbol('b'), 1);
+    expected_automata.add_transition(0, TransitionType::range('c', char::MAX), 1);
+    expected_automata.add_transition(2, TransitionType::range('\0', '`'), 3);
+    expected_automata.add_transition(2, TransitionType::symbol('a'), 3);
+    expected_automata.add_transition(2, TransitionType::symbol('b'), 4);
+    expected_automata.add_transition(2, TransitionType::range('c', char::MAX), 3);
+    for state in [1, 3, 4] {
+        expected_automata.add_transition(state, TransitionType::range('\0', '`'), 1);
+        expected_automata.add_transition(state, TransitionType::symbol('a'), 1);
+        expected_automata.add_transition(state, TransitionType::symbol('b'), 1);
+        expected_automata.add_transition(state, TransitionType::range('c', char::MAX), 1);
+    }
+
+    assert_eq!(automata, expected_automata)
+}
+
+#[test]
+fn test_resolve_assertions_splits_ranges_by_look_behind() {
+    let automata = resolve_assertions(
+        build_automata_from_ast(
+            parse_regex_with_config(
+                ".^",
+                &ParserConfig {
+                    multi_line: true,
+                    dot_matches_new_line: true,
+                    ..ParserConfig::default()
+                },
+            )
+            .unwrap(),
+            &mut 0,
+            usize::MAX,
+        )
+        .unwrap(),
+    );
+
+    // Only the branch that read a "\n" can pass the assertion, the other one is left without transitions
+    let mut expected = Automata::new(0);
+    expected.add_transition(0, TransitionType::Range(0, '\n' as u32), 2);
+    expected.add_transition(0, TransitionType::symbol('\n'), 1);
+    expected.add_transition(0, TransitionType::Range('\n' as u32 + 1, CODE_POINT_END), 2);
+    expected.add_transition(1, TransitionType::Epsilon, 4);
+    expected.add_transition(2, TransitionType::Epsilon, 3);
+    expected.add_transition(4, TransitionType::Epsilon, 5);
+    expected.add_final_state(5);
+
+    assert_eq!(automata, expected);
+}
+
+/// Check that the DFAs are the same up to the labels of the states, by walking both of them from the start states
+fn assert_isomorphic(dfa: &Dfa, other: &Dfa) {
+    assert_eq!(dfa.states().len(), other.states().len());
+
+    let transitions = dfa.transitions();
+    let other_transitions = other.transitions();
+    let mut labels: HashMap<State, State> = HashMap::from([(dfa.start_state(), other.start_state())]);
+    let mut work_list = vec![dfa.start_state()];
+
+    while let Some(state) = work_list.pop() {
+        let other_state = labels[&state];
+        assert_eq!(
+            dfa.final_states().contains(&state),
+            other.final_states().contains(&other_state)
+        );
+
+        let state_transitions: Vec<_> = transitions
+            .range((state, TransitionType::Epsilon)..(state + 1, TransitionType::Epsilon))
+            .collect();
+        let other_state_transitions: Vec<_> = other_transitions
+            .range((other_state, TransitionType::Epsilon)..(other_state + 1, TransitionType::Epsilon))
+            .collect();
+        assert_eq!(state_transitions.len(), other_state_transitions.len());
+
+        for ((&(_, transition), dest_states), (&(_, other_transition), other_dest_states)) in
+            state_transitions.into_iter().zip(other_state_transitions)
+        {
+            assert_eq!(transition, other_transition);
+            let dest_state = *dest_states.first().unwrap();
+            let other_dest_state = *other_dest_states.first().unwrap();
+
+            match labels.get(&dest_state) {
+                Some(&label) => assert_eq!(label, other_dest_state),
+                None => {
+                    labels.insert(dest_state, other_dest_state);
+                    work_list.push(dest_state);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_hopcroft_and_brzozowski_build_isomorphic_dfas() {
+    let parse = |regex: &str| build_automata_from_ast(parse_regex(regex).unwrap(), &mut 0, usize::MAX).unwrap();
+    let automata = [
+        create_automata(),
+        parse("[a-e]"),
+        parse("1[a-e]"),
+        parse("[^a]b"),
+        parse("[a-z]|[d-f]x"),
+        parse("(?s)a.|ab"),
+        parse("(a|b)*abb"),
+        parse("(ab|a)(bc|c)*"),
+        parse(""),
+        resolve_assertions(
+            build_automata_from_ast(
+                parse_regex_with_config(
+                    ".^",
+                    &ParserConfig {
+                        multi_line: true,
+                        dot_matches_new_line: true,
+                        ..ParserConfig::default()
+                    },
+                )
+                .unwrap(),
+                &mut 0,
+                usize::MAX,
+            )
+            .unwrap(),
+        ),
+    ];
+
+    for automata in automata {
+        let brzozowski = automata
+            .clone()
+            .convert_to_dfa(usize::MAX, Minimization::Brzozowski)
+            .unwrap();
+        let hopcroft = automata.convert_to_dfa(usize::MAX, Minimization::Hopcroft).unwrap();
+
+        assert_isomorphic(&brzozowski, &hopcroft);
+    }
+}
+
+#[test]
+fn test_hopcroft_merges_equivalent_states() {
+    // The subset construction creates the states before and after the "c" of each branch, but both branches
+    // accept the same suffix. Only the start, the states before and after a "c" and the dead state are left.
+    let automata = build_automata_from_ast(parse_regex("ac|bc").unwrap(), &mut 0, usize::MAX).unwrap();
+    let dfa = reachable(subset(automata, usize::MAX).unwrap());
+    let minimized = hopcroft(dfa.clone());
+
+    assert_eq!(dfa.states().len(), 6);
+    assert_eq!(minimized.states().len(), 4);
+}
+
+#[test]
+fn test_subset_with_thousands_of_states() {
+    // The DFA has to remember the last 12 characters, so every one of the 4096 sets of NFA states is a DFA state.
+    // The set of the start state also has the states before the first "(a|b)*", it's merged with the set that
+    // remembers 12 "b".
+    let regex = "(a|b)*a(a|b){11}";
+    let automata = build_automata_from_ast(parse_regex(regex).unwrap(), &mut 0, usize::MAX).unwrap();
+    let dfa = subset(automata.clone(), usize::MAX).unwrap();
+
+    assert_eq!(dfa.states().len(), 4097);
+    assert_eq!(dfa.states(), (0..4097).collect());
+    assert_eq!(hopcroft(dfa).states().len(), 4096);
+
+    let dfa = automata.convert_to_dfa(usize::MAX, Minimization::Hopcroft).unwrap();
+    for suffix in 0..4096 {
+        let text: String = (0..12)
+            .map(|bit| if suffix & (1 << bit) == 0 { 'a' } else { 'b' })
+            .collect();
+
+        assert_eq!(dfa.validate_str(&format!("ba{text}")), text.starts_with('a'));
+    }
+}
+
+#[test]
+fn test_utf8_automata_reads_the_bytes_of_the_characters() {
+    // "à" to "é" is C3 A0 to C3 A9 and "Ω" is CE A9
+    let automata = build_automata_from_ast(parse_regex("[à-é]|Ω|a").unwrap(), &mut 0, usize::MAX).unwrap();
+    let automata = utf8_automata(automata, usize::MAX).unwrap();
+    let dfa = automata.convert_to_dfa(usize::MAX, Minimization::Hopcroft).unwrap();
+
+    for (text, is_match) in [
+        (&b"a"[..], true),
+        ("à".as_bytes(), true),
+        ("é".as_bytes(), true),
+        ("ê".as_bytes(), false),
+        ("Ω".as_bytes(), true),
+        (b"\xC3", false),
+        (b"\xCE\xA9\xA9", false),
+    ] {
+        assert_eq!(dfa.validate(text.iter().map(|&byte| u32::from(byte))), is_match);
+    }
+}
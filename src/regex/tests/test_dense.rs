@@ -0,0 +1,131 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::regex::{
+    automata::*,
+    debug::AutomataDebug,
+    dense::SparseDfa,
+    parser::{parse_regex, parse_regex_with_config, ParserConfig},
+};
+
+fn build_nfa(regex: &str) -> Automata {
+    build_automata_from_ast(parse_regex(regex).unwrap(), &mut 0, usize::MAX).unwrap()
+}
+
+fn build_dfa(regex: &str, minimization: Minimization) -> Dfa {
+    build_nfa(regex).convert_to_dfa(usize::MAX, minimization).unwrap()
+}
+
+fn build_byte_dfa(regex: &str) -> Dfa {
+    let config = ParserConfig {
+        dot_matches_any_byte: true,
+        ..ParserConfig::default()
+    };
+    let automata = build_automata_from_ast(parse_regex_with_config(regex, &config).unwrap(), &mut 0, usize::MAX);
+
+    utf8_automata(automata.unwrap(), usize::MAX)
+        .unwrap()
+        .convert_to_dfa(usize::MAX, Minimization::Hopcroft)
+        .unwrap()
+}
+
+#[test]
+fn test_dense_table_agrees_with_the_nfa() {
+    let symbols = ['a', 'b', 'c', 'x', '1', '\n', 'é', 'Ω', '😀'];
+    let mut texts = vec![String::new()];
+    for _ in 0..3 {
+        let longer: Vec<String> = texts
+            .iter()
+            .flat_map(|text| symbols.iter().map(move |symbol| format!("{text}{symbol}")))
+            .collect();
+        texts.extend(longer);
+    }
+
+    for regex in [
+        "a",
+        "[a-cx]b?",
+        "(a|b)*abb",
+        "[^a]+",
+        "\\w\\d",
+        "(?s).é|Ω*",
+        "[à-é😀]+x",
+        "",
+    ] {
+        let nfa = build_nfa(regex);
+        for minimization in [Minimization::Brzozowski, Minimization::Hopcroft] {
+            let dfa = build_dfa(regex, minimization);
+
+            let sparse = SparseDfa::new(dfa.dense());
+
+            for text in &texts {
+                assert_eq!(dfa.validate_str(text), nfa.validate_str(text), "{regex} {text:?}");
+                assert_eq!(
+                    sparse.validate(text.chars().map(u32::from)),
+                    nfa.validate_str(text),
+                    "{regex} {text:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_ranges_with_the_same_transitions_share_a_class() {
+    // "a-c" and "x-z" are a class, "d" is another one and the rest of the characters the last one. The dead state
+    // of the DFA shares the row of the dead state of the table.
+    let dfa = build_dfa("[a-cx-z]d", Minimization::Hopcroft);
+    assert_eq!(dfa.dense().classes(), 3);
+    assert_eq!(dfa.dense().rows(), 4);
+
+    // The hundreds of ranges of "\w" are all in the same class
+    let dfa = build_dfa("\\w+", Minimization::Hopcroft);
+    assert_eq!(dfa.dense().classes(), 2);
+    assert_eq!(dfa.dense().rows(), 3);
+    assert!(dfa.validate_str("héllo_wörld"));
+    assert!(!dfa.validate_str("hello world"));
+}
+
+#[test]
+fn test_dense_table_transitions_by_ranges() {
+    // The transitions of a row are joined back into ranges, and the dead state is left out
+    let dfa = build_dfa("[a-cx-z]d", Minimization::Hopcroft);
+    let start = dfa.start_state();
+    let letter = dfa.transitions()[&(start, TransitionType::Range('a' as u32, 'd' as u32))]
+        .first()
+        .copied()
+        .unwrap();
+    let end = dfa.transitions()[&(letter, TransitionType::symbol('d'))]
+        .first()
+        .copied()
+        .unwrap();
+
+    let mut expected = BTreeMap::new();
+    expected.insert(
+        (start, TransitionType::Range('a' as u32, 'd' as u32)),
+        BTreeSet::from([letter]),
+    );
+    expected.insert(
+        (start, TransitionType::Range('x' as u32, '{' as u32)),
+        BTreeSet::from([letter]),
+    );
+    expected.insert((letter, TransitionType::symbol('d')), BTreeSet::from([end]));
+    assert_eq!(dfa.transitions(), expected);
+    assert_eq!(dfa.final_states(), BTreeSet::from([end]));
+    assert_eq!(dfa.states(), BTreeSet::from([start, letter, end]));
+}
+
+#[test]
+fn test_dense_table_of_byte_automata() {
+    let dfa = build_byte_dfa("[à-é]|Ω|.");
+
+    for (text, is_match) in [
+        (&b"a"[..], true),
+        (b"\xFF", true),
+        ("é".as_bytes(), true),
+        ("ê".as_bytes(), false),
+        ("Ω".as_bytes(), true),
+        (b"\xC3", true),
+        (b"\xCE\xA9\xA9", false),
+    ] {
+        assert_eq!(dfa.validate(text.iter().map(|&byte| u32::from(byte))), is_match);
+    }
+}
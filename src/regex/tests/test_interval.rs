@@ -0,0 +1,57 @@
+use crate::regex::interval::{partition, IntervalSet, CODE_POINT_END};
+
+#[test]
+fn test_interval_set_merges_overlapping_and_adjacent_intervals() {
+    let set = IntervalSet::new([(10, 20), (0, 5), (15, 30), (5, 8), (40, 40)]);
+
+    assert_eq!(set.intervals(), &[(0, 8), (10, 30)]);
+}
+
+#[test]
+fn test_interval_set_complement() {
+    let set = IntervalSet::from_range('a', 'z').union(&IntervalSet::from_range('0', '9'));
+
+    assert_eq!(
+        set.complement().intervals(),
+        &[
+            (0, '0' as u32),
+            ('9' as u32 + 1, 'a' as u32),
+            ('z' as u32 + 1, CODE_POINT_END)
+        ]
+    );
+    assert_eq!(set.complement().complement(), set);
+    assert_eq!(IntervalSet::default().complement().intervals(), &[(0, CODE_POINT_END)]);
+}
+
+#[test]
+fn test_partition() {
+    assert_eq!(
+        partition([(0, 10), (5, 15), (20, 30), (25, 26)]),
+        vec![(0, 5), (5, 10), (10, 15), (20, 25), (25, 26), (26, 30)]
+    );
+    assert_eq!(partition([(0, 10), (0, 10)]), vec![(0, 10)]);
+}
+
+#[test]
+fn test_interval_set_contains() {
+    let set = IntervalSet::from_range('a', 'c').union(&IntervalSet::from_range('x', 'z'));
+
+    assert!(set.contains('a'));
+    assert!(set.contains('c'));
+    assert!(set.contains('y'));
+    assert!(!set.contains('d'));
+    assert!(!set.contains('w'));
+    assert!(!set.contains('{'));
+}
+
+#[test]
+fn test_interval_set_intersection() {
+    let lhs = IntervalSet::new([(0, 10), (20, 30), (40, 50)]);
+    let rhs = IntervalSet::new([(5, 25), (29, 45)]);
+
+    assert_eq!(
+        lhs.intersection(&rhs).intervals(),
+        &[(5, 10), (20, 25), (29, 30), (40, 45)]
+    );
+    assert_eq!(lhs.intersection(&IntervalSet::default()), IntervalSet::default());
+}
@@ -0,0 +1,90 @@
+use crate::regex::{
+    automata::{Automata, SizeLimits},
+    lazy::LazyDfa,
+    parser::parse_regex,
+};
+
+fn lazy_dfa(regex: &str, cache_size: usize) -> LazyDfa {
+    LazyDfa::from_regex_expr(
+        parse_regex(regex).unwrap(),
+        SizeLimits {
+            dfa_states: cache_size,
+            ..SizeLimits::default()
+        },
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_lazy_dfa_only_creates_the_reached_states() {
+    let lazy_dfa = lazy_dfa("(a|b)*a(a|b){10}", 10_000);
+    assert_eq!(lazy_dfa.cached_states(), 1);
+
+    assert!(lazy_dfa.validate_str("aaaaaaaaaaa"));
+    assert_eq!(lazy_dfa.cached_states(), 12);
+
+    // The states are reused by the next texts
+    assert!(lazy_dfa.validate_str("aaaaaaaaaaa"));
+    assert!(!lazy_dfa.validate_str("aaaaaaaaaa"));
+    assert_eq!(lazy_dfa.cached_states(), 12);
+}
+
+#[test]
+fn test_lazy_dfa_clears_the_full_cache() {
+    let lazy_dfa = lazy_dfa("(a|b)*a(a|b){3}", 4);
+    let texts = ["abbb", "aabb", "babab", "bbbb", "abababa", "bbbbbbbbbabb", "a", ""];
+
+    for text in texts {
+        assert_eq!(
+            lazy_dfa.validate_str(text),
+            text.len() >= 4 && text.as_bytes()[text.len() - 4] == b'a'
+        );
+        assert!(lazy_dfa.cached_states() <= 4);
+    }
+    assert!(lazy_dfa.cache_clears() > 0);
+}
+
+#[test]
+fn test_lazy_dfa_searches_from_many_threads() {
+    let lazy_dfa = lazy_dfa("(a|b)*a(a|b){3}", 10_000);
+    let texts = ["abbb", "aabb", "babab", "bbbb", "abababa", "bbbbbbbbbabb", "a", ""];
+
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..100 {
+                    for text in texts {
+                        assert_eq!(
+                            lazy_dfa.validate_str(text),
+                            text.len() >= 4 && text.as_bytes()[text.len() - 4] == b'a'
+                        );
+                    }
+                }
+            });
+        }
+    });
+
+    // Every cache of the pool was used by a search that ran at the same time as the others
+    assert!((1..=4).contains(&lazy_dfa.caches()));
+    assert!(lazy_dfa.cached_states() <= 16);
+}
+
+#[test]
+fn test_lazy_dfa_matches_like_the_dfa() {
+    for regex in [
+        "[a-z]+|x[0-9]",
+        "(?s)a.|ab",
+        "[^a]b",
+        "(ab|a)(bc|c)*",
+        "\\w+\\b",
+        "(?m)^a$\\n^b$",
+    ] {
+        let dfa =
+            Automata::from_regex_expr(parse_regex(regex).unwrap(), SizeLimits::default(), Default::default()).unwrap();
+        let lazy_dfa = lazy_dfa(regex, 2);
+
+        for text in ["abc", "x1", "ab", "zb", "bb", "abcbc", "word", "a\nb", "", "\u{1F600}"] {
+            assert_eq!(lazy_dfa.validate_str(text), dfa.validate_str(text), "{regex} {text:?}");
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use crate::regex::lexer::{tokenize_regex_str, Token, TokenTypes};
+use crate::regex::{
+    class::{PerlClass, PosixClass},
+    lexer::{tokenize_regex_str, EscapeError, Flags, GroupError, Token, TokenTypes},
+};
 
 #[test]
 fn test_tokenize_regex_symbol() {
@@ -355,3 +358,384 @@ fn test_tokenize_dot_operator2() {
         ]
     )
 }
+
+#[test]
+fn test_tokenize_escaped_metacharacters() {
+    let regex = "\\.\\*";
+
+    assert_eq!(
+        tokenize_regex_str(regex),
+        vec![
+            Token::new(TokenTypes::Symbol('.'), 0, 2),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol('*'), 2, 4),
+            Token::new(TokenTypes::Eof, 5, 5)
+        ]
+    )
+}
+
+#[test]
+fn test_tokenize_control_and_hex_escapes() {
+    let regex = "\\n\\x41\\u{1F600}";
+
+    assert_eq!(
+        tokenize_regex_str(regex),
+        vec![
+            Token::new(TokenTypes::Symbol('\n'), 0, 2),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol('A'), 2, 6),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol('😀'), 6, 15),
+            Token::new(TokenTypes::Eof, 16, 16)
+        ]
+    )
+}
+
+#[test]
+fn test_tokenize_escapes_inside_character_class() {
+    let regex = "[\\]\\-a-z]";
+
+    assert_eq!(
+        tokenize_regex_str(regex),
+        vec![
+            Token::new(TokenTypes::OpenBracket, 0, 1),
+            Token::new(TokenTypes::Symbol(']'), 1, 3),
+            Token::new(TokenTypes::Union, 0, 0),
+            Token::new(TokenTypes::Symbol('-'), 3, 5),
+            Token::new(TokenTypes::Union, 0, 0),
+            Token::new(TokenTypes::Symbol('a'), 5, 6),
+            Token::new(TokenTypes::Dash, 6, 7),
+            Token::new(TokenTypes::Symbol('z'), 7, 8),
+            Token::new(TokenTypes::CloseBracket, 8, 9),
+            Token::new(TokenTypes::Eof, 10, 10)
+        ]
+    )
+}
+
+#[test]
+fn test_tokenize_invalid_escapes() {
+    assert_eq!(
+        tokenize_regex_str("a\\"),
+        vec![
+            Token::new(TokenTypes::Symbol('a'), 0, 1),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::InvalidEscape(EscapeError::Dangling), 1, 2),
+            Token::new(TokenTypes::Eof, 3, 3)
+        ]
+    );
+    assert_eq!(
+        tokenize_regex_str("\\q"),
+        vec![
+            Token::new(TokenTypes::InvalidEscape(EscapeError::Unknown('q')), 0, 2),
+            Token::new(TokenTypes::Eof, 3, 3)
+        ]
+    );
+    assert_eq!(
+        tokenize_regex_str("\\xZ"),
+        vec![
+            Token::new(TokenTypes::InvalidEscape(EscapeError::MalformedHex), 0, 2),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol('Z'), 2, 3),
+            Token::new(TokenTypes::Eof, 4, 4)
+        ]
+    );
+}
+
+#[test]
+fn test_tokenize_optional() {
+    let regex = "a?b";
+
+    assert_eq!(
+        tokenize_regex_str(regex),
+        vec![
+            Token::new(TokenTypes::Symbol('a'), 0, 1),
+            Token::new(TokenTypes::Optional, 1, 2),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol('b'), 2, 3),
+            Token::new(TokenTypes::Eof, 4, 4)
+        ]
+    )
+}
+
+#[test]
+fn test_tokenize_repetition() {
+    let regex = "a{3}b{2,}c{1,10}";
+
+    assert_eq!(
+        tokenize_regex_str(regex),
+        vec![
+            Token::new(TokenTypes::Symbol('a'), 0, 1),
+            Token::new(TokenTypes::Repetition(3, Some(3)), 1, 4),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol('b'), 4, 5),
+            Token::new(TokenTypes::Repetition(2, None), 5, 9),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol('c'), 9, 10),
+            Token::new(TokenTypes::Repetition(1, Some(10)), 10, 16),
+            Token::new(TokenTypes::Eof, 17, 17)
+        ]
+    )
+}
+
+#[test]
+fn test_tokenize_malformed_repetition_is_literal() {
+    let regex = "a{,2}";
+
+    assert_eq!(
+        tokenize_regex_str(regex),
+        vec![
+            Token::new(TokenTypes::Symbol('a'), 0, 1),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol('{'), 1, 2),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol(','), 2, 3),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol('2'), 3, 4),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol('}'), 4, 5),
+            Token::new(TokenTypes::Eof, 6, 6)
+        ]
+    )
+}
+
+#[test]
+fn test_tokenize_negated_character_class() {
+    let regex = "[^a^]";
+
+    assert_eq!(
+        tokenize_regex_str(regex),
+        vec![
+            Token::new(TokenTypes::OpenBracket, 0, 1),
+            Token::new(TokenTypes::Negation, 1, 2),
+            Token::new(TokenTypes::Symbol('a'), 2, 3),
+            Token::new(TokenTypes::Union, 0, 0),
+            Token::new(TokenTypes::Symbol('^'), 3, 4),
+            Token::new(TokenTypes::CloseBracket, 4, 5),
+            Token::new(TokenTypes::Eof, 6, 6),
+        ]
+    )
+}
+
+#[test]
+fn test_tokenize_named_and_non_capturing_groups() {
+    let regex = "(?<year>a)(?P<m>b)(?:c)";
+
+    assert_eq!(
+        tokenize_regex_str(regex),
+        vec![
+            Token::new(TokenTypes::NamedGroup(3, 7), 0, 8),
+            Token::new(TokenTypes::Symbol('a'), 8, 9),
+            Token::new(TokenTypes::CloseParenthesis, 9, 10),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::NamedGroup(14, 15), 10, 16),
+            Token::new(TokenTypes::Symbol('b'), 16, 17),
+            Token::new(TokenTypes::CloseParenthesis, 17, 18),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::NonCapturingGroup, 18, 21),
+            Token::new(TokenTypes::Symbol('c'), 21, 22),
+            Token::new(TokenTypes::CloseParenthesis, 22, 23),
+            Token::new(TokenTypes::Eof, 24, 24),
+        ]
+    )
+}
+
+#[test]
+fn test_tokenize_invalid_groups() {
+    assert_eq!(
+        tokenize_regex_str("(?<1a>)")[0],
+        Token::new(TokenTypes::InvalidGroup(GroupError::InvalidName), 0, 5)
+    );
+    assert_eq!(
+        tokenize_regex_str("(?<a")[0],
+        Token::new(TokenTypes::InvalidGroup(GroupError::InvalidName), 0, 4)
+    );
+    assert_eq!(
+        tokenize_regex_str("(?=a)")[0],
+        Token::new(TokenTypes::InvalidGroup(GroupError::Unknown), 0, 2)
+    );
+}
+
+#[test]
+fn test_tokenize_word_boundaries() {
+    let regex = "\\ba\\B";
+
+    assert_eq!(
+        tokenize_regex_str(regex),
+        vec![
+            Token::new(TokenTypes::WordBoundary, 0, 2),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol('a'), 2, 3),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::NotWordBoundary, 3, 5),
+            Token::new(TokenTypes::Eof, 6, 6),
+        ]
+    )
+}
+
+#[test]
+fn test_tokenize_anchors() {
+    let regex = "^a$\\A\\z[$^]";
+
+    assert_eq!(
+        tokenize_regex_str(regex),
+        vec![
+            Token::new(TokenTypes::Caret, 0, 1),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol('a'), 1, 2),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Dollar, 2, 3),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::StartOfText, 3, 5),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::EndOfText, 5, 7),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::OpenBracket, 7, 8),
+            Token::new(TokenTypes::Symbol('$'), 8, 9),
+            Token::new(TokenTypes::Union, 0, 0),
+            Token::new(TokenTypes::Symbol('^'), 9, 10),
+            Token::new(TokenTypes::CloseBracket, 10, 11),
+            Token::new(TokenTypes::Eof, 12, 12),
+        ]
+    )
+}
+
+#[test]
+fn test_tokenize_perl_classes() {
+    let regex = "\\d\\W[\\w-]";
+
+    assert_eq!(
+        tokenize_regex_str(regex),
+        vec![
+            Token::new(TokenTypes::PerlClass(PerlClass::Digit, false), 0, 2),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::PerlClass(PerlClass::Word, true), 2, 4),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::OpenBracket, 4, 5),
+            Token::new(TokenTypes::PerlClass(PerlClass::Word, false), 5, 7),
+            Token::new(TokenTypes::Union, 0, 0),
+            Token::new(TokenTypes::Symbol('-'), 7, 8),
+            Token::new(TokenTypes::CloseBracket, 8, 9),
+            Token::new(TokenTypes::Eof, 10, 10),
+        ]
+    )
+}
+
+#[test]
+fn test_tokenize_posix_classes() {
+    let regex = "[[:alpha:][:^digit:]_[:foo:][:a]";
+
+    assert_eq!(
+        tokenize_regex_str(regex),
+        vec![
+            Token::new(TokenTypes::OpenBracket, 0, 1),
+            Token::new(TokenTypes::PosixClass(PosixClass::Alpha, false), 1, 10),
+            Token::new(TokenTypes::Union, 0, 0),
+            Token::new(TokenTypes::PosixClass(PosixClass::Digit, true), 10, 20),
+            Token::new(TokenTypes::Union, 0, 0),
+            Token::new(TokenTypes::Symbol('_'), 20, 21),
+            Token::new(TokenTypes::Union, 0, 0),
+            Token::new(TokenTypes::InvalidPosixClass, 21, 28),
+            Token::new(TokenTypes::Union, 0, 0),
+            Token::new(TokenTypes::UnclosedPosixClass, 28, 31),
+            Token::new(TokenTypes::CloseBracket, 31, 32),
+            Token::new(TokenTypes::Eof, 33, 33),
+        ]
+    )
+}
+
+#[test]
+fn test_tokenize_unicode_classes() {
+    let regex = "\\pL\\P{Greek}[\\p{N}_]\\p{}";
+
+    assert_eq!(
+        tokenize_regex_str(regex),
+        vec![
+            Token::new(TokenTypes::UnicodeClass(2, 3, false), 0, 3),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::UnicodeClass(6, 11, true), 3, 12),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::OpenBracket, 12, 13),
+            Token::new(TokenTypes::UnicodeClass(16, 17, false), 13, 18),
+            Token::new(TokenTypes::Union, 0, 0),
+            Token::new(TokenTypes::Symbol('_'), 18, 19),
+            Token::new(TokenTypes::CloseBracket, 19, 20),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::InvalidEscape(EscapeError::MalformedUnicodeClass), 20, 22),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol('{'), 22, 23),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol('}'), 23, 24),
+            Token::new(TokenTypes::Eof, 25, 25),
+        ]
+    )
+}
+
+#[test]
+fn test_tokenize_flags() {
+    let regex = "a(?i)b(?s-m:c)(?j)(?ii)";
+    let case_insensitive = Flags {
+        case_insensitive: true,
+        ..Flags::default()
+    };
+    let dot_matches_new_line = Flags {
+        dot_matches_new_line: true,
+        ..Flags::default()
+    };
+    let multi_line = Flags {
+        multi_line: true,
+        ..Flags::default()
+    };
+
+    assert_eq!(
+        tokenize_regex_str(regex),
+        vec![
+            Token::new(TokenTypes::Symbol('a'), 0, 1),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::SetFlags(case_insensitive, Flags::default()), 1, 5),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol('b'), 5, 6),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::FlagGroup(dot_matches_new_line, multi_line), 6, 12),
+            Token::new(TokenTypes::Symbol('c'), 12, 13),
+            Token::new(TokenTypes::CloseParenthesis, 13, 14),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::InvalidGroup(GroupError::UnknownFlag('j')), 16, 17),
+            Token::new(TokenTypes::CloseParenthesis, 17, 18),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::InvalidGroup(GroupError::DuplicateFlag('i')), 21, 22),
+            Token::new(TokenTypes::CloseParenthesis, 22, 23),
+            Token::new(TokenTypes::Eof, 24, 24),
+        ]
+    )
+}
+
+#[test]
+fn test_tokenize_verbose_mode() {
+    let regex = "(?x) a # b\n [ ]\\ (?-x: c)";
+    let verbose = Flags {
+        verbose: true,
+        ..Flags::default()
+    };
+
+    assert_eq!(
+        tokenize_regex_str(regex),
+        vec![
+            Token::new(TokenTypes::SetFlags(verbose, Flags::default()), 0, 4),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol('a'), 5, 6),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::OpenBracket, 12, 13),
+            Token::new(TokenTypes::Symbol(' '), 13, 14),
+            Token::new(TokenTypes::CloseBracket, 14, 15),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol(' '), 15, 17),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::FlagGroup(Flags::default(), verbose), 17, 22),
+            Token::new(TokenTypes::Symbol(' '), 22, 23),
+            Token::new(TokenTypes::Concatenation, 0, 0),
+            Token::new(TokenTypes::Symbol('c'), 23, 24),
+            Token::new(TokenTypes::CloseParenthesis, 24, 25),
+            Token::new(TokenTypes::Eof, 26, 26),
+        ]
+    )
+}
@@ -0,0 +1,76 @@
+use crate::regex::{
+    interval::IntervalSet,
+    nfa::{Instruction, Program},
+    parser::parse_regex,
+};
+
+#[test]
+fn test_program_union() {
+    let program = Program::new(parse_regex("a|b").unwrap());
+
+    assert_eq!(
+        program.instructions,
+        vec![
+            Instruction::Save(0),
+            Instruction::Split(2, 4),
+            Instruction::Ranges(IntervalSet::from_range('a', 'a')),
+            Instruction::Jump(5),
+            Instruction::Ranges(IntervalSet::from_range('b', 'b')),
+            Instruction::Save(1),
+            Instruction::Match,
+        ]
+    );
+}
+
+#[test]
+fn test_program_closures() {
+    let program = Program::new(parse_regex("a*b+").unwrap());
+
+    assert_eq!(
+        program.instructions,
+        vec![
+            Instruction::Save(0),
+            Instruction::Split(2, 4),
+            Instruction::Ranges(IntervalSet::from_range('a', 'a')),
+            Instruction::Jump(1),
+            Instruction::Ranges(IntervalSet::from_range('b', 'b')),
+            Instruction::Split(4, 6),
+            Instruction::Save(1),
+            Instruction::Match,
+        ]
+    );
+}
+
+#[test]
+fn test_program_optional() {
+    let program = Program::new(parse_regex("a?").unwrap());
+
+    assert_eq!(
+        program.instructions,
+        vec![
+            Instruction::Save(0),
+            Instruction::Split(2, 3),
+            Instruction::Ranges(IntervalSet::from_range('a', 'a')),
+            Instruction::Save(1),
+            Instruction::Match,
+        ]
+    );
+}
+
+#[test]
+fn test_program_capture_groups() {
+    let program = Program::new(parse_regex("(a)(b){0}").unwrap());
+
+    assert_eq!(program.slots, 6);
+    assert_eq!(
+        program.instructions,
+        vec![
+            Instruction::Save(0),
+            Instruction::Save(2),
+            Instruction::Ranges(IntervalSet::from_range('a', 'a')),
+            Instruction::Save(3),
+            Instruction::Save(1),
+            Instruction::Match,
+        ]
+    );
+}
@@ -1,4 +1,8 @@
-use crate::regex::parser::{parse_regex, BinaryOp, CharacterClassBinaryOp, CharacterClassType, RegexAST, UnaryOp};
+use crate::regex::interval::IntervalSet;
+use crate::regex::parser::{
+    parse_regex, parse_regex_with_config, Assertion, BinaryOp, CharacterClassBinaryOp, CharacterClassType,
+    ParserConfig, RegexAST, UnaryOp,
+};
 
 #[test]
 fn test_parse_symbol() {
@@ -82,16 +86,47 @@ fn test_parse_multiple_union() {
     )
 }
 
+#[test]
+fn test_parse_long_chains_are_balanced() {
+    let symbol = |symbol| Box::new(RegexAST::Symbol(symbol));
+    let binary = |lhs, op, rhs| Box::new(RegexAST::Binary(lhs, op, rhs));
+
+    assert_eq!(
+        parse_regex("abcd").unwrap(),
+        *binary(
+            binary(symbol('a'), BinaryOp::Concatenation, symbol('b')),
+            BinaryOp::Concatenation,
+            binary(symbol('c'), BinaryOp::Concatenation, symbol('d'))
+        )
+    );
+    assert_eq!(
+        parse_regex("a|b|c|d|e").unwrap(),
+        *binary(
+            binary(
+                binary(symbol('a'), BinaryOp::Union, symbol('b')),
+                BinaryOp::Union,
+                symbol('c')
+            ),
+            BinaryOp::Union,
+            binary(symbol('d'), BinaryOp::Union, symbol('e'))
+        )
+    );
+}
+
 #[test]
 fn test_parse_closurestar_with_parens() {
     let expr = parse_regex("(ab)*").unwrap();
     assert_eq!(
         expr,
         RegexAST::Unary(
-            Box::new(RegexAST::Binary(
-                Box::new(RegexAST::Symbol('a')),
-                BinaryOp::Concatenation,
-                Box::new(RegexAST::Symbol('b'))
+            Box::new(RegexAST::Group(
+                Box::new(RegexAST::Binary(
+                    Box::new(RegexAST::Symbol('a')),
+                    BinaryOp::Concatenation,
+                    Box::new(RegexAST::Symbol('b'))
+                )),
+                1,
+                None
             )),
             UnaryOp::ClosureStar
         )
@@ -105,10 +140,14 @@ fn test_parse_closurestar_with_parens_and_union() {
         expr,
         RegexAST::Binary(
             Box::new(RegexAST::Unary(
-                Box::new(RegexAST::Binary(
-                    Box::new(RegexAST::Symbol('a')),
-                    BinaryOp::Concatenation,
-                    Box::new(RegexAST::Symbol('b'))
+                Box::new(RegexAST::Group(
+                    Box::new(RegexAST::Binary(
+                        Box::new(RegexAST::Symbol('a')),
+                        BinaryOp::Concatenation,
+                        Box::new(RegexAST::Symbol('b'))
+                    )),
+                    1,
+                    None
                 )),
                 UnaryOp::ClosureStar
             )),
@@ -143,19 +182,27 @@ fn test_parse_closurestar_union_closureplus() {
         expr,
         RegexAST::Binary(
             Box::new(RegexAST::Unary(
-                Box::new(RegexAST::Binary(
-                    Box::new(RegexAST::Symbol('a')),
-                    BinaryOp::Concatenation,
-                    Box::new(RegexAST::Symbol('b'))
+                Box::new(RegexAST::Group(
+                    Box::new(RegexAST::Binary(
+                        Box::new(RegexAST::Symbol('a')),
+                        BinaryOp::Concatenation,
+                        Box::new(RegexAST::Symbol('b'))
+                    )),
+                    1,
+                    None
                 )),
                 UnaryOp::ClosureStar
             )),
             BinaryOp::Union,
             Box::new(RegexAST::Unary(
-                Box::new(RegexAST::Binary(
-                    Box::new(RegexAST::Symbol('c')),
-                    BinaryOp::Concatenation,
-                    Box::new(RegexAST::Symbol('d'))
+                Box::new(RegexAST::Group(
+                    Box::new(RegexAST::Binary(
+                        Box::new(RegexAST::Symbol('c')),
+                        BinaryOp::Concatenation,
+                        Box::new(RegexAST::Symbol('d'))
+                    )),
+                    2,
+                    None
                 )),
                 UnaryOp::ClosurePlus
             ))
@@ -170,26 +217,30 @@ fn test_parse_union_with_paren_and_concatenation() {
     assert_eq!(
         expr,
         RegexAST::Binary(
-            Box::new(RegexAST::Binary(
+            Box::new(RegexAST::Group(
                 Box::new(RegexAST::Binary(
                     Box::new(RegexAST::Binary(
-                        Box::new(RegexAST::Symbol('a')),
-                        BinaryOp::Concatenation,
-                        Box::new(RegexAST::Symbol('b'))
+                        Box::new(RegexAST::Binary(
+                            Box::new(RegexAST::Symbol('a')),
+                            BinaryOp::Concatenation,
+                            Box::new(RegexAST::Symbol('b'))
+                        )),
+                        BinaryOp::Union,
+                        Box::new(RegexAST::Binary(
+                            Box::new(RegexAST::Symbol('c')),
+                            BinaryOp::Concatenation,
+                            Box::new(RegexAST::Symbol('d'))
+                        ))
                     )),
                     BinaryOp::Union,
                     Box::new(RegexAST::Binary(
-                        Box::new(RegexAST::Symbol('c')),
+                        Box::new(RegexAST::Symbol('e')),
                         BinaryOp::Concatenation,
-                        Box::new(RegexAST::Symbol('d'))
+                        Box::new(RegexAST::Symbol('f'))
                     ))
                 )),
-                BinaryOp::Union,
-                Box::new(RegexAST::Binary(
-                    Box::new(RegexAST::Symbol('e')),
-                    BinaryOp::Concatenation,
-                    Box::new(RegexAST::Symbol('f'))
-                ))
+                1,
+                None
             )),
             BinaryOp::Concatenation,
             Box::new(RegexAST::Symbol('g'))
@@ -300,7 +351,7 @@ fn test_parse_character_class_with_three_ranges() {
 
 #[test]
 fn test_parse_dot_operator() {
-    let expr = parse_regex("..").unwrap();
+    let expr = parse_regex("(?s)..").unwrap();
 
     assert_eq!(
         expr,
@@ -311,3 +362,262 @@ fn test_parse_dot_operator() {
         )
     )
 }
+
+#[test]
+fn test_parse_dot_operator_without_new_line() {
+    let expr = parse_regex(".").unwrap();
+
+    assert_eq!(
+        expr,
+        RegexAST::CharacterClass(CharacterClassType::Negated(Box::new(CharacterClassType::Single('\n'))))
+    )
+}
+
+#[test]
+fn test_parse_escaped_metacharacters() {
+    let expr = parse_regex("\\(\\)").unwrap();
+
+    assert_eq!(
+        expr,
+        RegexAST::Binary(
+            Box::new(RegexAST::Symbol('(')),
+            BinaryOp::Concatenation,
+            Box::new(RegexAST::Symbol(')'))
+        )
+    )
+}
+
+#[test]
+fn test_parse_escape_inside_character_class() {
+    let expr = parse_regex("[\\[-\\]]").unwrap();
+
+    assert_eq!(
+        expr,
+        RegexAST::CharacterClass(CharacterClassType::Binary(
+            Box::new(CharacterClassType::Single('[')),
+            CharacterClassBinaryOp::Range,
+            Box::new(CharacterClassType::Single(']'))
+        ))
+    )
+}
+
+#[test]
+fn test_parse_optional() {
+    let expr = parse_regex("ab?").unwrap();
+
+    assert_eq!(
+        expr,
+        RegexAST::Binary(
+            Box::new(RegexAST::Symbol('a')),
+            BinaryOp::Concatenation,
+            Box::new(RegexAST::Unary(Box::new(RegexAST::Symbol('b')), UnaryOp::Optional))
+        )
+    )
+}
+
+#[test]
+fn test_parse_repetition() {
+    let expr = parse_regex("(ab){2,}").unwrap();
+
+    assert_eq!(
+        expr,
+        RegexAST::Unary(
+            Box::new(RegexAST::Group(
+                Box::new(RegexAST::Binary(
+                    Box::new(RegexAST::Symbol('a')),
+                    BinaryOp::Concatenation,
+                    Box::new(RegexAST::Symbol('b'))
+                )),
+                1,
+                None
+            )),
+            UnaryOp::Repetition { min: 2, max: None }
+        )
+    )
+}
+
+#[test]
+fn test_parse_negated_character_class() {
+    let expr = parse_regex("[^a-z]").unwrap();
+
+    assert_eq!(
+        expr,
+        RegexAST::CharacterClass(CharacterClassType::Negated(Box::new(CharacterClassType::Binary(
+            Box::new(CharacterClassType::Single('a')),
+            CharacterClassBinaryOp::Range,
+            Box::new(CharacterClassType::Single('z'))
+        ))))
+    )
+}
+
+#[test]
+fn test_parse_nested_groups_are_numbered_by_opening_parenthesis() {
+    let expr = parse_regex("((a)b)(c)").unwrap();
+
+    assert_eq!(
+        expr,
+        RegexAST::Binary(
+            Box::new(RegexAST::Group(
+                Box::new(RegexAST::Binary(
+                    Box::new(RegexAST::Group(Box::new(RegexAST::Symbol('a')), 2, None)),
+                    BinaryOp::Concatenation,
+                    Box::new(RegexAST::Symbol('b'))
+                )),
+                1,
+                None
+            )),
+            BinaryOp::Concatenation,
+            Box::new(RegexAST::Group(Box::new(RegexAST::Symbol('c')), 3, None))
+        )
+    )
+}
+
+#[test]
+fn test_parse_named_and_non_capturing_groups() {
+    let expr = parse_regex("(?:a)(?<b>b)(c)").unwrap();
+
+    assert_eq!(
+        expr,
+        RegexAST::Binary(
+            Box::new(RegexAST::Symbol('a')),
+            BinaryOp::Concatenation,
+            Box::new(RegexAST::Binary(
+                Box::new(RegexAST::Group(
+                    Box::new(RegexAST::Symbol('b')),
+                    1,
+                    Some("b".to_string())
+                )),
+                BinaryOp::Concatenation,
+                Box::new(RegexAST::Group(Box::new(RegexAST::Symbol('c')), 2, None))
+            ))
+        )
+    )
+}
+
+#[test]
+fn test_parse_anchors() {
+    let expr = parse_regex("^a$").unwrap();
+
+    assert_eq!(
+        expr,
+        RegexAST::Binary(
+            Box::new(RegexAST::Assertion(Assertion::StartText)),
+            BinaryOp::Concatenation,
+            Box::new(RegexAST::Binary(
+                Box::new(RegexAST::Symbol('a')),
+                BinaryOp::Concatenation,
+                Box::new(RegexAST::Assertion(Assertion::EndText))
+            ))
+        )
+    )
+}
+
+#[test]
+fn test_parse_anchors_multi_line() {
+    let config = ParserConfig {
+        multi_line: true,
+        ..ParserConfig::default()
+    };
+    let expr = parse_regex_with_config("^\\A|$\\z", &config).unwrap();
+
+    assert_eq!(
+        expr,
+        RegexAST::Binary(
+            Box::new(RegexAST::Binary(
+                Box::new(RegexAST::Assertion(Assertion::StartLine)),
+                BinaryOp::Concatenation,
+                Box::new(RegexAST::Assertion(Assertion::StartText))
+            )),
+            BinaryOp::Union,
+            Box::new(RegexAST::Binary(
+                Box::new(RegexAST::Assertion(Assertion::EndLine)),
+                BinaryOp::Concatenation,
+                Box::new(RegexAST::Assertion(Assertion::EndText))
+            ))
+        )
+    )
+}
+
+#[test]
+fn test_parse_ascii_perl_classes() {
+    let config = ParserConfig {
+        unicode: false,
+        ..ParserConfig::default()
+    };
+    let expr = parse_regex_with_config("[\\D_]", &config).unwrap();
+
+    assert_eq!(
+        expr,
+        RegexAST::CharacterClass(CharacterClassType::Binary(
+            Box::new(CharacterClassType::Negated(Box::new(CharacterClassType::Set(
+                IntervalSet::from_range('0', '9')
+            )))),
+            CharacterClassBinaryOp::Union,
+            Box::new(CharacterClassType::Single('_'))
+        ))
+    )
+}
+
+#[test]
+fn test_parse_case_insensitive_flag() {
+    let expr = parse_regex("(a(?i)k)1k").unwrap();
+
+    assert_eq!(
+        expr,
+        RegexAST::Binary(
+            Box::new(RegexAST::Group(
+                Box::new(RegexAST::Binary(
+                    Box::new(RegexAST::Symbol('a')),
+                    BinaryOp::Concatenation,
+                    Box::new(RegexAST::CharacterClass(CharacterClassType::Set(IntervalSet::new([
+                        ('K' as u32, 'K' as u32 + 1),
+                        ('k' as u32, 'k' as u32 + 1),
+                        (0x212A, 0x212B)
+                    ]))))
+                )),
+                1,
+                None
+            )),
+            BinaryOp::Concatenation,
+            Box::new(RegexAST::Binary(
+                Box::new(RegexAST::Symbol('1')),
+                BinaryOp::Concatenation,
+                Box::new(RegexAST::Symbol('k'))
+            ))
+        )
+    )
+}
+
+#[test]
+fn test_parse_case_insensitive_negated_class() {
+    let config = ParserConfig {
+        case_insensitive: true,
+        ..ParserConfig::default()
+    };
+    let expr = parse_regex_with_config("[^a-c]", &config).unwrap();
+
+    assert_eq!(
+        expr,
+        RegexAST::CharacterClass(CharacterClassType::Negated(Box::new(CharacterClassType::Set(
+            IntervalSet::new([('A' as u32, 'D' as u32), ('a' as u32, 'd' as u32)])
+        ))))
+    )
+}
+
+#[test]
+fn test_parse_scoped_flags() {
+    let expr = parse_regex("(?m:^(?-m)^)^").unwrap();
+
+    assert_eq!(
+        expr,
+        RegexAST::Binary(
+            Box::new(RegexAST::Binary(
+                Box::new(RegexAST::Assertion(Assertion::StartLine)),
+                BinaryOp::Concatenation,
+                Box::new(RegexAST::Assertion(Assertion::StartText))
+            )),
+            BinaryOp::Concatenation,
+            Box::new(RegexAST::Assertion(Assertion::StartText))
+        )
+    )
+}
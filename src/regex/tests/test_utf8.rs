@@ -0,0 +1,66 @@
+use crate::regex::{interval::CODE_POINT_END, utf8::utf8_sequences};
+
+fn sequences_match(sequences: &[Vec<(u8, u8)>], bytes: &[u8]) -> usize {
+    sequences
+        .iter()
+        .filter(|sequence| {
+            sequence.len() == bytes.len()
+                && sequence
+                    .iter()
+                    .zip(bytes)
+                    .all(|(&(start, end), byte)| (start..=end).contains(byte))
+        })
+        .count()
+}
+
+#[test]
+fn test_utf8_sequences_of_all_characters() {
+    assert_eq!(
+        utf8_sequences(0, CODE_POINT_END),
+        vec![
+            vec![(0x00, 0x7F)],
+            vec![(0xC2, 0xDF), (0x80, 0xBF)],
+            vec![(0xE0, 0xE0), (0xA0, 0xBF), (0x80, 0xBF)],
+            vec![(0xE1, 0xEC), (0x80, 0xBF), (0x80, 0xBF)],
+            vec![(0xED, 0xED), (0x80, 0x9F), (0x80, 0xBF)],
+            vec![(0xEE, 0xEF), (0x80, 0xBF), (0x80, 0xBF)],
+            vec![(0xF0, 0xF0), (0x90, 0xBF), (0x80, 0xBF), (0x80, 0xBF)],
+            vec![(0xF1, 0xF3), (0x80, 0xBF), (0x80, 0xBF), (0x80, 0xBF)],
+            vec![(0xF4, 0xF4), (0x80, 0x8F), (0x80, 0xBF), (0x80, 0xBF)],
+        ]
+    );
+}
+
+#[test]
+fn test_utf8_sequences_single_character() {
+    assert_eq!(utf8_sequences('a' as u32, 'a' as u32 + 1), vec![vec![(b'a', b'a')]]);
+    assert_eq!(
+        utf8_sequences(0x1F600, 0x1F601),
+        vec![vec![(0xF0, 0xF0), (0x9F, 0x9F), (0x98, 0x98), (0x80, 0x80)]]
+    );
+    assert_eq!(utf8_sequences(0xD800, 0xE000), Vec::<Vec<(u8, u8)>>::new());
+}
+
+#[test]
+fn test_utf8_sequences_match_exactly_the_characters_of_the_range() {
+    for (start, end) in [
+        (0x41, 0x5B),
+        (0x370, 0x400),
+        (0x7F0, 0x900),
+        (0xD000, 0xF000),
+        (0xFFF0, 0x10010),
+    ] {
+        let sequences = utf8_sequences(start, end);
+
+        for code_point in (start.saturating_sub(0x100)..end + 0x100).filter_map(char::from_u32) {
+            let mut bytes = [0; 4];
+            let matches = sequences_match(&sequences, code_point.encode_utf8(&mut bytes).as_bytes());
+
+            assert_eq!(
+                matches,
+                usize::from((start..end).contains(&(code_point as u32))),
+                "{code_point:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,10 @@
+//! Unicode tables generated by `scripts/generate_unicode_tables.pl`, every table is a sorted list of disjoint
+//! inclusive ranges of characters.
+
+pub mod case_folding;
+pub mod general_category;
+pub mod perl_decimal;
+pub mod perl_space;
+pub mod perl_word;
+pub mod script;
+pub mod version;
@@ -0,0 +1,4 @@
+// DO NOT EDIT: generated by scripts/generate_unicode_tables.pl from Unicode 14.0.0
+
+/// The version of the Unicode standard the tables are generated from
+pub const UNICODE_VERSION: (u8, u8, u8) = (14, 0, 0);
@@ -0,0 +1,83 @@
+use super::interval::CODE_POINT_END;
+
+/// The first and the last surrogate code points, they aren't characters so they don't have a UTF-8 encoding
+const SURROGATES: (u32, u32) = (0xD800, 0xDFFF);
+/// The biggest code point encoded with one, two and three bytes
+const ENCODING_ENDS: [u32; 3] = [0x7F, 0x7FF, 0xFFFF];
+
+/// A sequence of inclusive byte ranges, it matches the UTF-8 encoding of a character if every byte of the encoding
+/// is in the range at the same position
+pub type Utf8Sequence = Vec<(u8, u8)>;
+
+/// Split the characters of the half-open interval of code points `[start, end)` in sequences of byte ranges, that
+/// together match the UTF-8 encodings of all the characters and nothing else. The sequences are disjoint, and are
+/// sorted by their first byte range.
+///
+/// A range like `[\u{80}-\u{10FFFF}]` can't be matched by a single sequence, e.g. `[C2-F4][80-BF]` also matches
+/// `F4 80` that isn't the encoding of any character. So the range is first split where the length of the encoding
+/// changes, and then until the continuation bytes of every piece go from `80` to `BF` except the ones that only
+/// have a single value.
+/// Reference: https://github.com/rust-lang/regex/blob/master/regex-syntax/src/utf8.rs
+pub fn utf8_sequences(start: u32, end: u32) -> Vec<Utf8Sequence> {
+    let mut sequences = Vec::new();
+    // The ranges to split are inclusive, the ones that are smaller are always the next to be split so the sequences
+    // are found sorted
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    if start < end.min(CODE_POINT_END) {
+        ranges.push((start, end.min(CODE_POINT_END) - 1));
+    }
+
+    'ranges: while let Some((start, end)) = ranges.pop() {
+        if start <= SURROGATES.1 && SURROGATES.0 <= end {
+            push_split(&mut ranges, (start, SURROGATES.0 - 1), (SURROGATES.1 + 1, end));
+            continue;
+        }
+
+        for encoding_end in ENCODING_ENDS {
+            if start <= encoding_end && encoding_end < end {
+                push_split(&mut ranges, (start, encoding_end), (encoding_end + 1, end));
+                continue 'ranges;
+            }
+        }
+
+        // Every continuation byte has 6 bits of the code point, the bytes after the first one that changes have to
+        // cover all the continuation bytes
+        for continuation_bytes in 1..4 {
+            let mask = (1 << (6 * continuation_bytes)) - 1;
+            if start & !mask == end & !mask {
+                continue;
+            }
+            if start & mask != 0 {
+                push_split(&mut ranges, (start, start | mask), ((start | mask) + 1, end));
+                continue 'ranges;
+            }
+            if end & mask != mask {
+                push_split(&mut ranges, (start, (end & !mask) - 1), (end & !mask, end));
+                continue 'ranges;
+            }
+        }
+
+        let (mut start_bytes, mut end_bytes) = ([0; 4], [0; 4]);
+        let start_bytes = encode(start, &mut start_bytes);
+        let end_bytes = encode(end, &mut end_bytes);
+        sequences.push(start_bytes.iter().copied().zip(end_bytes.iter().copied()).collect());
+    }
+
+    sequences
+}
+
+/// Push the two halves of a range so the first one is split first, ignoring the empty ones
+fn push_split(ranges: &mut Vec<(u32, u32)>, first: (u32, u32), second: (u32, u32)) {
+    for (start, end) in [second, first] {
+        if start <= end {
+            ranges.push((start, end));
+        }
+    }
+}
+
+fn encode(code_point: u32, bytes: &mut [u8; 4]) -> &[u8] {
+    // The surrogates were split out of the ranges, so every code point is a character
+    let symbol = char::from_u32(code_point).unwrap();
+
+    symbol.encode_utf8(bytes).as_bytes()
+}
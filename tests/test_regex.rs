@@ -1,4 +1,6 @@
-use regex::regex::{Error, Regex};
+use std::borrow::Cow;
+
+use regex::regex::{bytes, Captures, Engine, Error, Minimization, NoExpand, Regex, RegexBuilder, UNICODE_VERSION};
 
 #[test]
 fn test_regex_match_a() {
@@ -175,6 +177,128 @@ fn test_invalid_group_regex2() {
     )
 }
 
+#[test]
+fn test_invalid_duplicated_group_name_regex() {
+    let re = Regex::new("(?<x>a)|(?P<x>b)");
+
+    assert_eq!(
+        re.unwrap_err(),
+        Error::Syntax("Invalid group name: the name \"x\" at position 8 is already used by another group!".to_string())
+    )
+}
+
+#[test]
+fn test_invalid_group_name_regex() {
+    let re = Regex::new("a(?<1>b)");
+
+    assert_eq!(
+        re.unwrap_err(),
+        Error::Syntax(
+            "Invalid group name at position 1: a name must start with a letter or \"_\", followed by letters, digits or \"_\", and end with \">\"!"
+                .to_string()
+        )
+    )
+}
+
+#[test]
+fn test_invalid_group_kind_regex() {
+    let re = Regex::new("(?=a)");
+
+    assert_eq!(
+        re.unwrap_err(),
+        Error::Syntax(
+            "Invalid group at position 0: \"(?\" must be followed by \":\", \"<name>\", \"P<name>\", \"flags)\" or \"flags:\"!"
+                .to_string()
+        )
+    )
+}
+
+#[test]
+fn test_invalid_flag_regex() {
+    assert_eq!(
+        Regex::new("a(?iq)").unwrap_err(),
+        Error::Syntax(
+            "Invalid flag at position 4: \"q\" isn't a known flag, the flags are \"i\", \"m\", \"s\" and \"x\"!"
+                .to_string()
+        )
+    );
+    assert_eq!(
+        Regex::new("(?i-mi:a)").unwrap_err(),
+        Error::Syntax("Invalid flag at position 5: \"i\" appears more than once in the same group!".to_string())
+    );
+    assert_eq!(
+        Regex::new("(?i-m-s)").unwrap_err(),
+        Error::Syntax("Invalid flag at position 5: \"-\" appears more than once in the same group!".to_string())
+    );
+    assert!(Regex::new("(?-)").is_err());
+    assert!(Regex::new("(?i").is_err());
+}
+
+#[test]
+fn test_repeated_flags_regex() {
+    assert_eq!(
+        Regex::new("(?i)*").unwrap_err(),
+        Error::Syntax(
+            "Invalid repetition at position 4: the flags \"(?i)\" don't match anything, so they can't be repeated!"
+                .to_string()
+        )
+    );
+    assert!(Regex::new("a(?s-m){2}").is_err());
+    assert!(Regex::new("(?i)a*").is_ok());
+}
+
+#[test]
+fn test_unclosed_named_group_regex() {
+    for regex in ["(?P", "(?Pa)", "a(?P>a)"] {
+        let position = regex.find('(').unwrap();
+        assert_eq!(
+            Regex::new(regex).unwrap_err(),
+            Error::Syntax(format!(
+                "Invalid group at position {position}: the named group \"(?P\" isn't followed by \"<name>\"!"
+            ))
+        );
+    }
+}
+
+#[test]
+fn test_invalid_posix_class_regex() {
+    let re = Regex::new("[[:foo:]]");
+
+    assert_eq!(
+        re.unwrap_err(),
+        Error::Syntax("Invalid character class: \"[:foo:]\" at position 1 isn't a known POSIX class!".to_string())
+    )
+}
+
+#[test]
+fn test_unclosed_posix_class_regex() {
+    assert_eq!(
+        Regex::new("[[:alpha]]").unwrap_err(),
+        Error::Syntax("Invalid character class: \"[:alpha\" at position 1 isn't closed by \":]\"!".to_string())
+    );
+    assert!(Regex::new("[a[:b]").is_err());
+    assert_eq!(
+        Regex::new("a]").unwrap_err(),
+        Error::Syntax("Unmatched bracket.".to_string())
+    );
+}
+
+#[test]
+fn test_invalid_unicode_class_regex() {
+    assert_eq!(
+        Regex::new("a\\p{Foo}").unwrap_err(),
+        Error::Syntax(
+            "Invalid unicode class: \"Foo\" at position 1 isn't a known general category or script!".to_string()
+        )
+    );
+    assert_eq!(
+        Regex::new("\\p{Greek").unwrap_err(),
+        Error::Syntax(
+            "Invalid escape sequence at position 0: expected \"\\p{...}\" with the name of a general category or script, or \"\\p\" followed by a one letter category!".to_string()
+        )
+    );
+}
+
 #[test]
 fn test_invalid_closure_regex() {
     let re = Regex::new("*");
@@ -234,3 +358,1303 @@ fn test_regex_dot_operator2() {
     assert!(re.is_match("9😼g"));
     assert!(re.is_match("6$i"));
 }
+
+#[test]
+fn test_regex_escaped_metacharacters() {
+    let re = Regex::new("a\\.b\\+\\(c\\)\\\\").unwrap();
+
+    assert!(re.is_match("a.b+(c)\\"));
+    assert!(!re.is_match("axb+(c)\\"));
+    assert!(!re.is_match("a.bb(c)\\"));
+}
+
+#[test]
+fn test_regex_escapes_inside_character_class() {
+    let re = Regex::new("[\\]\\[.*-]+").unwrap();
+
+    assert!(re.is_match("[].*-"));
+    assert!(!re.is_match("a"));
+}
+
+#[test]
+fn test_regex_control_and_hex_escapes() {
+    let re = Regex::new("\\t\\x41\\u{1F600}\\n").unwrap();
+
+    assert!(re.is_match("\tA😀\n"));
+    assert!(!re.is_match("tA😀n"));
+}
+
+#[test]
+fn test_invalid_dangling_escape_regex() {
+    let re = Regex::new("ab\\");
+
+    assert_eq!(
+        re.unwrap_err(),
+        Error::Syntax("Invalid escape sequence: \"\\\" at position 2 isn't escaping any character!".to_string())
+    )
+}
+
+#[test]
+fn test_invalid_unknown_escape_regex() {
+    let re = Regex::new("[a\\q]");
+
+    assert_eq!(
+        re.unwrap_err(),
+        Error::Syntax("Invalid escape sequence: \"\\q\" at position 2 isn't a known escape sequence!".to_string())
+    )
+}
+
+#[test]
+fn test_invalid_hex_escape_regex() {
+    assert!(Regex::new("\\x4").is_err());
+    assert!(Regex::new("\\u{}").is_err());
+    assert!(Regex::new("\\u{1F600").is_err());
+    assert_eq!(
+        Regex::new("\\u{D800}").unwrap_err(),
+        Error::Syntax(
+            "Invalid escape sequence at position 0: \"D800\" isn't a valid unicode scalar value!".to_string()
+        )
+    )
+}
+
+#[test]
+fn test_regex_optional() {
+    let re = Regex::new("colou?r").unwrap();
+
+    assert!(re.is_match("color"));
+    assert!(re.is_match("colour"));
+    assert!(!re.is_match("colouur"));
+}
+
+#[test]
+fn test_regex_exact_repetition() {
+    let re = Regex::new("[0-9]{3}-[0-9]{4}").unwrap();
+
+    assert!(re.is_match("555-1234"));
+    assert!(!re.is_match("55-1234"));
+    assert!(!re.is_match("5555-1234"));
+    assert!(!re.is_match("555-123"));
+}
+
+#[test]
+fn test_regex_bounded_repetition() {
+    let re = Regex::new("a{2,4}").unwrap();
+
+    assert!(!re.is_match("a"));
+    assert!(re.is_match("aa"));
+    assert!(re.is_match("aaa"));
+    assert!(re.is_match("aaaa"));
+    assert!(!re.is_match("aaaaa"));
+}
+
+#[test]
+fn test_regex_unbounded_repetition() {
+    let re = Regex::new("(ab){2,}").unwrap();
+
+    assert!(!re.is_match("ab"));
+    assert!(re.is_match("abab"));
+    assert!(re.is_match("abababababab"));
+}
+
+#[test]
+fn test_regex_zero_repetition() {
+    let re = Regex::new("ba{0}").unwrap();
+
+    assert!(re.is_match("b"));
+    assert!(!re.is_match("ba"));
+}
+
+#[test]
+fn test_invalid_repetition_range_regex() {
+    let re = Regex::new("a{3,2}");
+
+    assert_eq!(
+        re.unwrap_err(),
+        Error::InvalidRange("Invalid Repetition: \"3\" is bigger than \"2\"!".to_string())
+    )
+}
+
+#[test]
+fn test_invalid_optional_regex() {
+    let re = Regex::new("?");
+
+    assert_eq!(
+        re.unwrap_err(),
+        Error::Syntax(
+            "Invalid Optional: Optional operator needs a preceding literal, e.g. \"a?\", \"(ab)?\", \"(a|c)?\"."
+                .to_string()
+        )
+    )
+}
+
+#[test]
+fn test_repetition_limit() {
+    let re = Regex::new("a{1,100000}");
+
+    assert_eq!(
+        re.unwrap_err(),
+        Error::Syntax("Invalid Repetition: \"100000\" exceeds the repetition limit of 1000!".to_string())
+    );
+
+    let re = RegexBuilder::new("a{3,5}").repetition_limit(4).build();
+    assert_eq!(
+        re.unwrap_err(),
+        Error::Syntax("Invalid Repetition: \"5\" exceeds the repetition limit of 4!".to_string())
+    );
+
+    let re = RegexBuilder::new("a{3,5}").repetition_limit(5).build().unwrap();
+    assert!(re.is_match("aaaaa"));
+}
+
+#[test]
+fn test_regex_negated_character_class() {
+    let re = Regex::new("[^,\"]+").unwrap();
+
+    assert!(re.is_match("field"));
+    assert!(re.is_match("😼 with spaces"));
+    assert!(!re.is_match("a,b"));
+    assert!(!re.is_match("\"quoted\""));
+    assert!(!re.is_match(""));
+}
+
+#[test]
+fn test_regex_negated_character_class_with_overlapping_symbols() {
+    let re = Regex::new("[^a-c]x[^x]").unwrap();
+
+    assert!(re.is_match("xxa"));
+    assert!(re.is_match("dxd"));
+    assert!(!re.is_match("axd"));
+    assert!(!re.is_match("dxx"));
+}
+
+#[test]
+fn test_regex_literal_caret_inside_character_class() {
+    let re = Regex::new("[a^]+").unwrap();
+
+    assert!(re.is_match("a^a"));
+    assert!(!re.is_match("b"));
+}
+
+#[test]
+fn test_invalid_empty_negated_character_class_regex() {
+    let re = Regex::new("[^]");
+
+    assert_eq!(
+        re.unwrap_err(),
+        Error::Syntax("Invalid character class: the character class ending at position 2 is empty!".to_string())
+    )
+}
+
+#[test]
+fn test_regex_huge_character_class() {
+    let re = Regex::new("[\\u{0}-\\u{10FFFF}]+").unwrap();
+
+    assert!(re.is_match("any text 😼"));
+    assert!(!re.is_match(""));
+}
+
+#[test]
+fn test_regex_cjk_range() {
+    let re = Regex::new("[\\u{4E00}-\\u{9FFF}]+[0-9]").unwrap();
+
+    assert!(re.is_match("漢字1"));
+    assert!(!re.is_match("漢a1"));
+}
+
+#[test]
+fn test_regex_overlapping_ranges() {
+    let re = Regex::new("[a-z]+[d-f]x").unwrap();
+
+    assert!(re.is_match("aaex"));
+    assert!(re.is_match("dddx"));
+    assert!(!re.is_match("aaax"));
+}
+
+#[test]
+fn test_regex_dot_overlapping_with_symbol() {
+    let re = Regex::new(".b").unwrap();
+
+    assert!(re.is_match("bb"));
+    assert!(re.is_match("ab"));
+    assert!(!re.is_match("ba"));
+}
+
+#[test]
+fn test_regex_dot_and_symbol_in_different_branches() {
+    let re = Regex::new("a.c|abd").unwrap();
+
+    assert!(re.is_match("abc"));
+    assert!(re.is_match("abd"));
+    assert!(re.is_match("axc"));
+    assert!(!re.is_match("axd"));
+}
+
+#[test]
+fn test_regex_dot_closure_followed_by_symbol() {
+    let re = Regex::new(".*x.*").unwrap();
+
+    assert!(re.is_match("x"));
+    assert!(re.is_match("aaxbb"));
+    assert!(re.is_match("xxx"));
+    assert!(!re.is_match("aabb"));
+}
+
+#[test]
+fn test_regex_negated_class_overlapping_with_dot() {
+    let re = Regex::new("[^a]b|.c").unwrap();
+
+    assert!(re.is_match("bb"));
+    assert!(re.is_match("ac"));
+    assert!(re.is_match("cc"));
+    assert!(!re.is_match("ab"));
+}
+
+#[test]
+fn test_regex_find_substring() {
+    let re = Regex::new("[0-9]+").unwrap();
+    let m = re.find("abc 123 456").unwrap();
+
+    assert_eq!(m.start(), 4);
+    assert_eq!(m.end(), 7);
+    assert_eq!(m.range(), 4..7);
+    assert_eq!(m.as_str(), "123");
+}
+
+#[test]
+fn test_regex_find_no_match() {
+    let re = Regex::new("[0-9]+").unwrap();
+
+    assert_eq!(re.find("abc"), None);
+    assert_eq!(re.find(""), None);
+}
+
+#[test]
+fn test_regex_find_leftmost_first() {
+    assert_eq!(Regex::new("a|ab").unwrap().find("ab").unwrap().as_str(), "a");
+    assert_eq!(Regex::new("ab|a").unwrap().find("ab").unwrap().as_str(), "ab");
+    assert_eq!(Regex::new("b|ab").unwrap().find("xab").unwrap().as_str(), "ab");
+}
+
+#[test]
+fn test_regex_find_greedy_closures() {
+    let re = Regex::new("a+").unwrap();
+
+    assert_eq!(re.find("baaab").unwrap().as_str(), "aaa");
+    assert_eq!(Regex::new("a.*b").unwrap().find("xaxbxbx").unwrap().as_str(), "axbxb");
+}
+
+#[test]
+fn test_regex_find_empty_match() {
+    let re = Regex::new("a*").unwrap();
+    let m = re.find("bbb").unwrap();
+
+    assert_eq!(m.range(), 0..0);
+    assert!(m.is_empty());
+    assert_eq!(re.find("").unwrap().range(), 0..0);
+}
+
+#[test]
+fn test_regex_find_closure_star_of_empty_body() {
+    // The body is tried before leaving the loop, and it can match the empty string first
+    let re = Regex::new(r"(?:\b|c)*").unwrap();
+
+    assert_eq!(re.find("c").unwrap().range(), 0..0);
+    assert_eq!(
+        re.find_iter("c").map(|m| m.range()).collect::<Vec<_>>(),
+        vec![0..0, 1..1]
+    );
+}
+
+#[test]
+fn test_regex_find_unicode_byte_offsets() {
+    let re = Regex::new("[^a-z]+").unwrap();
+    let m = re.find("ab→✓cd").unwrap();
+
+    assert_eq!(m.range(), 2..8);
+    assert_eq!(m.as_str(), "→✓");
+}
+
+#[test]
+fn test_regex_find_doesnt_change_is_match() {
+    let re = Regex::new("b").unwrap();
+
+    assert!(re.find("abc").is_some());
+    assert!(!re.is_match("abc"));
+}
+
+#[test]
+fn test_regex_find_iter() {
+    let re = Regex::new("[0-9]+").unwrap();
+    let matches: Vec<_> = re.find_iter("a1 22 b333").map(|m| (m.range(), m.as_str())).collect();
+
+    assert_eq!(matches, vec![(1..2, "1"), (3..5, "22"), (7..10, "333")]);
+}
+
+#[test]
+fn test_regex_find_iter_no_match() {
+    let re = Regex::new("x").unwrap();
+
+    assert_eq!(re.find_iter("abc").count(), 0);
+}
+
+#[test]
+fn test_regex_find_iter_empty_matches() {
+    let re = Regex::new("a*").unwrap();
+    let matches: Vec<_> = re.find_iter("baaab").map(|m| m.range()).collect();
+
+    assert_eq!(matches, vec![0..0, 1..4, 5..5]);
+}
+
+#[test]
+fn test_regex_find_iter_empty_matches_at_utf8_boundaries() {
+    let re = Regex::new("x*").unwrap();
+    let matches: Vec<_> = re.find_iter("→é").map(|m| m.range()).collect();
+
+    assert_eq!(matches, vec![0..0, 3..3, 5..5]);
+}
+
+#[test]
+fn test_regex_searches_share_the_regex() {
+    // Every search takes its own memory from the regex, so the iterators can be nested and the regex can be used
+    // from many threads at the same time
+    let re = Regex::new("(\\w)(\\w*)").unwrap();
+    let haystack = "one two three";
+    let pairs: Vec<_> = re
+        .find_iter(haystack)
+        .flat_map(|outer| {
+            re.find_iter(haystack)
+                .map(move |inner| (outer.as_str(), inner.as_str()))
+        })
+        .collect();
+    assert_eq!(pairs.len(), 9);
+    assert_eq!(pairs[5], ("two", "three"));
+
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..100 {
+                    assert_eq!(re.find(haystack).unwrap().as_str(), "one");
+                    assert_eq!(re.captures("  two").unwrap().get(2).unwrap().as_str(), "wo");
+                    assert_eq!(re.find_iter(haystack).count(), 3);
+                }
+            });
+        }
+    });
+
+    let re = bytes::Regex::new("\\w+").unwrap();
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..100 {
+                    assert_eq!(re.find(b"\xFF word").unwrap().range(), 2..6);
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn test_regex_split() {
+    let re = Regex::new(", *").unwrap();
+    let parts: Vec<_> = re.split("a, b,c,  d").collect();
+
+    assert_eq!(parts, vec!["a", "b", "c", "d"]);
+}
+
+#[test]
+fn test_regex_split_leading_and_trailing_matches() {
+    let re = Regex::new(",").unwrap();
+
+    assert_eq!(re.split(",a,").collect::<Vec<_>>(), vec!["", "a", ""]);
+    assert_eq!(re.split("").collect::<Vec<_>>(), vec![""]);
+}
+
+#[test]
+fn test_regex_split_on_empty_matches() {
+    let re = Regex::new("x*").unwrap();
+
+    assert_eq!(re.split("aé").collect::<Vec<_>>(), vec!["", "a", "é", ""]);
+}
+
+#[test]
+fn test_regex_splitn() {
+    let re = Regex::new(" +").unwrap();
+
+    assert_eq!(re.splitn("a b  c d", 3).collect::<Vec<_>>(), vec!["a", "b", "c d"]);
+    assert_eq!(re.splitn("a b", 5).collect::<Vec<_>>(), vec!["a", "b"]);
+    assert_eq!(re.splitn("a b", 1).collect::<Vec<_>>(), vec!["a b"]);
+    assert_eq!(re.splitn("a b", 0).count(), 0);
+}
+
+#[test]
+fn test_regex_captures() {
+    let re = Regex::new("([a-z]+)-([0-9]+)").unwrap();
+    let caps = re.captures("id: abc-123!").unwrap();
+
+    assert_eq!(caps.len(), 3);
+    assert_eq!(caps.get(0).unwrap().as_str(), "abc-123");
+    assert_eq!(caps.get(1).unwrap().as_str(), "abc");
+    assert_eq!(caps.get(1).unwrap().range(), 4..7);
+    assert_eq!(caps.get(2).unwrap().as_str(), "123");
+    assert_eq!(caps.get(3), None);
+}
+
+#[test]
+fn test_regex_captures_group_not_participating() {
+    let re = Regex::new("(a)|(b)").unwrap();
+    let caps = re.captures("b").unwrap();
+
+    assert_eq!(caps.get(1), None);
+    assert_eq!(caps.get(2).unwrap().as_str(), "b");
+}
+
+#[test]
+fn test_regex_captures_repeated_group_keeps_last_iteration() {
+    let re = Regex::new("(a|b)+").unwrap();
+    let caps = re.captures("abba").unwrap();
+
+    assert_eq!(caps.get(0).unwrap().as_str(), "abba");
+    assert_eq!(caps.get(1).unwrap().range(), 3..4);
+}
+
+#[test]
+fn test_regex_captures_closure_star_of_empty_body() {
+    // The empty iteration goes through the group, so it records an empty span
+    let re = Regex::new("(?:(x?))*").unwrap();
+
+    assert_eq!(re.captures("").unwrap().get(1).unwrap().range(), 0..0);
+    assert_eq!(re.captures("y").unwrap().get(1).unwrap().range(), 0..0);
+    assert_eq!(re.captures("xx").unwrap().get(1).unwrap().range(), 1..2);
+}
+
+#[test]
+fn test_regex_captures_nested_groups() {
+    let re = Regex::new("((a*)b)").unwrap();
+    let caps = re.captures("xaab").unwrap();
+
+    assert_eq!(caps.get(1).unwrap().as_str(), "aab");
+    assert_eq!(caps.get(2).unwrap().as_str(), "aa");
+}
+
+#[test]
+fn test_regex_captures_no_match() {
+    let re = Regex::new("(a)").unwrap();
+
+    assert_eq!(re.captures("b"), None);
+}
+
+#[test]
+fn test_regex_captures_iter() {
+    let re = Regex::new("([a-z])=([0-9])").unwrap();
+    let pairs: Vec<_> = re
+        .captures_iter("a=1, b=2,c=3")
+        .map(|caps| (caps.get(1).unwrap().as_str(), caps.get(2).unwrap().as_str()))
+        .collect();
+
+    assert_eq!(pairs, vec![("a", "1"), ("b", "2"), ("c", "3")]);
+}
+
+#[test]
+fn test_regex_named_captures() {
+    let re = Regex::new("(?<year>[0-9]{4})-(?P<month>[0-9]{2})-([0-9]{2})").unwrap();
+    let caps = re.captures("date: 2024-05-17").unwrap();
+
+    assert_eq!(caps.name("year").unwrap().as_str(), "2024");
+    assert_eq!(caps.name("month").unwrap().as_str(), "05");
+    assert_eq!(caps.get(1).unwrap().as_str(), "2024");
+    assert_eq!(caps.get(3).unwrap().as_str(), "17");
+    assert_eq!(caps.name("day"), None);
+}
+
+#[test]
+fn test_regex_non_capturing_group_doesnt_shift_indices() {
+    let re = Regex::new("(?:a|b)+(c)").unwrap();
+    let caps = re.captures("abac").unwrap();
+
+    assert_eq!(caps.len(), 2);
+    assert_eq!(caps.get(0).unwrap().as_str(), "abac");
+    assert_eq!(caps.get(1).unwrap().as_str(), "c");
+}
+
+#[test]
+fn test_regex_capture_names() {
+    let re = Regex::new("(?<first>a)(b)(?:c)(?P<last>d)").unwrap();
+
+    assert_eq!(
+        re.capture_names().collect::<Vec<_>>(),
+        vec![None, Some("first"), None, Some("last")]
+    );
+}
+
+#[test]
+fn test_regex_replace_first_match() {
+    let re = Regex::new("[0-9]+").unwrap();
+
+    assert_eq!(re.replace("a1 b22 c333", "#"), "a# b22 c333");
+}
+
+#[test]
+fn test_regex_replace_all() {
+    let re = Regex::new("[0-9]+").unwrap();
+
+    assert_eq!(re.replace_all("a1 b22 c333", "#"), "a# b# c#");
+    assert_eq!(re.replacen("a1 b22 c333", 2, "#"), "a# b# c333");
+}
+
+#[test]
+fn test_regex_replace_without_match_borrows() {
+    let re = Regex::new("[0-9]+").unwrap();
+
+    assert!(matches!(re.replace_all("abc", "#"), Cow::Borrowed("abc")));
+    assert!(matches!(re.replace_all("a1", "#"), Cow::Owned(_)));
+}
+
+#[test]
+fn test_regex_replace_template_expansion() {
+    let re = Regex::new("(?<key>[a-z]+)=([0-9]+)").unwrap();
+
+    assert_eq!(re.replace_all("a=1 bc=23", "$2:${key}"), "1:a 23:bc");
+    assert_eq!(re.replace_all("a=1", "${2}0 $key"), "10 a");
+    assert_eq!(re.replace_all("a=1", "$$1 costs $"), "$1 costs $");
+}
+
+#[test]
+fn test_regex_replace_template_missing_groups_are_empty() {
+    let re = Regex::new("(a)|(b)").unwrap();
+
+    assert_eq!(re.replace_all("ab", "[$1|$2|$3|$name]"), "[a|||][|b||]");
+}
+
+#[test]
+fn test_regex_replace_with_closure() {
+    let re = Regex::new("[0-9]+").unwrap();
+    let replaced = re.replace_all("a1 b22", |caps: &Captures| {
+        let number: u32 = caps.get(0).unwrap().as_str().parse().unwrap();
+        (number * 2).to_string()
+    });
+
+    assert_eq!(replaced, "a2 b44");
+}
+
+#[test]
+fn test_regex_replace_no_expand() {
+    let re = Regex::new("(a)").unwrap();
+
+    assert_eq!(re.replace_all("bab", NoExpand("$1")), "b$1b");
+}
+
+#[test]
+fn test_regex_replace_empty_matches() {
+    let re = Regex::new("x*").unwrap();
+
+    assert_eq!(re.replace_all("aé", "-"), "-a-é-");
+}
+
+#[test]
+fn test_regex_anchors_with_is_match() {
+    let re = Regex::new("^ab$").unwrap();
+
+    assert!(re.is_match("ab"));
+    assert!(!re.is_match("abab"));
+    assert!(!Regex::new("a^b").unwrap().is_match("ab"));
+    assert!(Regex::new("a$|b").unwrap().is_match("a"));
+}
+
+#[test]
+fn test_regex_anchors_with_find() {
+    let re = Regex::new("^[0-9]+").unwrap();
+
+    assert_eq!(re.find("12 34").unwrap().as_str(), "12");
+    assert_eq!(re.find("a12"), None);
+
+    let re = Regex::new("[0-9]+$").unwrap();
+    assert_eq!(re.find("12 34").unwrap().as_str(), "34");
+    assert_eq!(re.find("12\n34\n"), None);
+}
+
+#[test]
+fn test_regex_text_anchors_ignore_multi_line() {
+    let re = RegexBuilder::new("\\A[a-z]+\\z").multi_line(true).build().unwrap();
+
+    assert_eq!(re.find("ab\ncd"), None);
+    assert_eq!(re.find("ab").unwrap().as_str(), "ab");
+}
+
+#[test]
+fn test_regex_multi_line_anchors() {
+    let re = RegexBuilder::new("^[a-z]+$").multi_line(true).build().unwrap();
+    let lines: Vec<_> = re.find_iter("ab\n12\ncd\n").map(|m| m.as_str()).collect();
+
+    assert_eq!(lines, vec!["ab", "cd"]);
+    assert_eq!(Regex::new("^[a-z]+$").unwrap().find("ab\ncd"), None);
+}
+
+#[test]
+fn test_regex_multi_line_anchors_with_is_match() {
+    let re = RegexBuilder::new("a$\n^b").multi_line(true).build().unwrap();
+
+    assert!(re.is_match("a\nb"));
+    assert!(!Regex::new("a$\n^b").unwrap().is_match("a\nb"));
+    assert!(!RegexBuilder::new("a$b")
+        .multi_line(true)
+        .build()
+        .unwrap()
+        .is_match("ab"));
+}
+
+#[test]
+fn test_regex_empty_matches_of_anchors() {
+    let re = RegexBuilder::new("^").multi_line(true).build().unwrap();
+    let starts: Vec<_> = re.find_iter("a\nb\n").map(|m| m.start()).collect();
+
+    assert_eq!(starts, vec![0, 2, 4]);
+    assert_eq!(Regex::new("$").unwrap().replace_all("ab", "!"), "ab!");
+}
+
+#[test]
+fn test_invalid_anchor_in_character_class_regex() {
+    let re = Regex::new("[\\A]");
+
+    assert_eq!(
+        re.unwrap_err(),
+        Error::Syntax("Invalid escape sequence: \"\\A\" at position 1 isn't a known escape sequence!".to_string())
+    )
+}
+
+#[test]
+fn test_regex_word_boundary_with_find() {
+    let re = Regex::new("\\b[a-z]+\\b").unwrap();
+    let words: Vec<_> = re.find_iter("foo, bar3 baz!").map(|m| m.as_str()).collect();
+
+    assert_eq!(words, vec!["foo", "baz"]);
+}
+
+#[test]
+fn test_regex_not_word_boundary_with_find() {
+    let re = Regex::new("\\Boo\\B").unwrap();
+
+    assert_eq!(re.find("oo foo fooo").unwrap().range(), 8..10);
+}
+
+#[test]
+fn test_regex_word_boundary_is_unicode_aware() {
+    let re = Regex::new("\\b.+\\b").unwrap();
+
+    assert_eq!(re.find("«été»").unwrap().as_str(), "été");
+    assert_eq!(Regex::new("\\bд\\b").unwrap().find("дом д").unwrap().range(), 7..9);
+}
+
+#[test]
+fn test_regex_word_boundary_with_is_match() {
+    let re = Regex::new("[a-z]+\\b.*").unwrap();
+
+    assert!(re.is_match("ab cd"));
+    assert!(re.is_match("ab"));
+    assert!(re.is_match("ab!"));
+    assert!(!Regex::new("a\\bb").unwrap().is_match("ab"));
+    assert!(Regex::new("a\\Bb").unwrap().is_match("ab"));
+    assert!(Regex::new("a\\b é").unwrap().is_match("a é"));
+    assert!(!Regex::new("é\\bé").unwrap().is_match("éé"));
+}
+
+#[test]
+fn test_regex_empty_word_boundaries() {
+    let re = Regex::new("\\b").unwrap();
+    let boundaries: Vec<_> = re.find_iter("ab cd").map(|m| m.start()).collect();
+
+    assert_eq!(boundaries, vec![0, 2, 3, 5]);
+    assert!(!re.is_match(""));
+    assert!(Regex::new("\\B").unwrap().is_match(""));
+}
+
+#[test]
+fn test_regex_perl_classes() {
+    assert!(Regex::new("\\d+").unwrap().is_match("0123"));
+    assert!(Regex::new("\\w+").unwrap().is_match("a_Z9"));
+    assert!(Regex::new("\\s+").unwrap().is_match(" \t\r\n"));
+    assert!(!Regex::new("\\d").unwrap().is_match("a"));
+    assert!(!Regex::new("\\w").unwrap().is_match("-"));
+    assert!(!Regex::new("\\s").unwrap().is_match("a"));
+}
+
+#[test]
+fn test_regex_negated_perl_classes() {
+    let re = Regex::new("\\D\\W\\S").unwrap();
+
+    assert!(re.is_match("a-b"));
+    assert!(!re.is_match("1-b"));
+    assert!(!re.is_match("aab"));
+    assert!(!re.is_match("a- "));
+}
+
+#[test]
+fn test_regex_perl_classes_inside_brackets() {
+    let re = Regex::new("[\\w-]+").unwrap();
+
+    assert_eq!(re.find("  foo-bar_1 baz").unwrap().as_str(), "foo-bar_1");
+    assert_eq!(Regex::new("[^\\s,]+").unwrap().find(" ,ab,c").unwrap().as_str(), "ab");
+    assert!(Regex::new("[\\d\\s]+").unwrap().is_match("1 2\t3"));
+}
+
+#[test]
+fn test_regex_perl_classes_are_unicode_by_default() {
+    assert!(Regex::new("\\d").unwrap().is_match("٣"));
+    assert!(Regex::new("\\w+").unwrap().is_match("été"));
+    assert!(Regex::new("\\s").unwrap().is_match("\u{3000}"));
+    assert!(!Regex::new("\\W").unwrap().is_match("é"));
+}
+
+#[test]
+fn test_regex_ascii_perl_classes() {
+    let build = |pattern: &str| RegexBuilder::new(pattern).unicode(false).build().unwrap();
+
+    assert!(!build("\\d").is_match("٣"));
+    assert!(!build("\\w").is_match("é"));
+    assert!(build("\\W").is_match("é"));
+    assert!(!build("\\s").is_match("\u{3000}"));
+    assert!(build("\\s").is_match("\u{b}"));
+}
+
+#[test]
+fn test_regex_ascii_word_boundary() {
+    let builders = |pattern: &str| {
+        let mut builders = [
+            RegexBuilder::new(pattern),
+            RegexBuilder::new(pattern),
+            RegexBuilder::new(pattern),
+        ];
+        builders[1].dfa_size_limit(0);
+        builders[2].lazy_dfa(true);
+        builders
+    };
+
+    for mut builder in builders("\\Bé\\B") {
+        assert!(!builder.build().unwrap().is_match("é"));
+        assert!(builder.unicode(false).build().unwrap().is_match("é"));
+    }
+    for mut builder in builders("a\\bé") {
+        assert!(!builder.build().unwrap().is_match("aé"));
+        assert!(builder.unicode(false).build().unwrap().is_match("aé"));
+    }
+
+    let find = |pattern: &str, unicode: bool, haystack: &str| {
+        let re = RegexBuilder::new(pattern).unicode(unicode).build().unwrap();
+        re.find(haystack).map(|m| m.range())
+    };
+    assert_eq!(find("\\b", true, "é"), Some(0..0));
+    assert_eq!(find("\\b", false, "é"), None);
+    assert_eq!(find("\\b.\\b", false, "éa"), Some(2..3));
+}
+
+#[test]
+fn test_regex_posix_classes() {
+    let cases = [
+        ("alnum", "aZ0", "_"),
+        ("alpha", "aZ", "0"),
+        ("blank", " \t", "\n"),
+        ("cntrl", "\0\u{1f}\u{7f}", " "),
+        ("digit", "09", "a"),
+        ("graph", "!~a", " "),
+        ("lower", "az", "A"),
+        ("print", " ~", "\t"),
+        ("punct", "!/:@[`{~", "a"),
+        ("space", " \t\n\r\u{b}\u{c}", "a"),
+        ("upper", "AZ", "a"),
+        ("xdigit", "09afAF", "g"),
+    ];
+
+    for (name, matching, not_matching) in cases {
+        let re = Regex::new(&format!("[[:{name}:]]+")).unwrap();
+        let negated = Regex::new(&format!("[[:^{name}:]]+")).unwrap();
+
+        assert!(re.is_match(matching), "{name} should match {matching:?}");
+        assert!(!re.is_match(not_matching), "{name} shouldn't match {not_matching:?}");
+        assert!(negated.is_match(not_matching), "^{name} should match {not_matching:?}");
+        assert!(!negated.is_match(matching), "^{name} shouldn't match {matching:?}");
+    }
+}
+
+#[test]
+fn test_regex_posix_classes_with_other_items() {
+    let re = Regex::new("[[:digit:]a-f_]+").unwrap();
+
+    assert_eq!(re.find("xx09af_g").unwrap().as_str(), "09af_");
+    assert!(!Regex::new("[[:alpha:]]").unwrap().is_match("é"));
+    assert!(Regex::new("[[:a]").is_err());
+}
+
+#[test]
+fn test_regex_unicode_classes() {
+    let cases = [
+        ("L", "aZéж中", "0 _"),
+        ("Letter", "aZéж中", "0"),
+        ("Lu", "AÉЖ", "aé"),
+        ("Uppercase_Letter", "AÉЖ", "aé"),
+        ("N", "09٣Ⅻ½", "a"),
+        ("Nd", "09٣", "Ⅻ½"),
+        ("gc=Nd", "09٣", "Ⅻ"),
+        ("General_Category:Decimal Number", "09٣", "Ⅻ"),
+        ("Greek", "αβΩ", "ab"),
+        ("Grek", "αβΩ", "ж"),
+        ("sc=Cyrillic", "жЖ", "a"),
+        ("Script=Han", "中文", "a"),
+        ("P", "!,.", "a"),
+        ("Zs", " \u{3000}", "\t"),
+    ];
+
+    for (name, matching, not_matching) in cases {
+        let re = Regex::new(&format!("^\\p{{{name}}}+$")).unwrap();
+        let negated = Regex::new(&format!("^\\P{{{name}}}+$")).unwrap();
+
+        assert!(re.is_match(matching), "{name} should match {matching:?}");
+        assert!(!re.is_match(not_matching), "{name} shouldn't match {not_matching:?}");
+        assert!(negated.is_match(not_matching), "^{name} should match {not_matching:?}");
+        assert!(!negated.is_match(matching), "^{name} shouldn't match {matching:?}");
+    }
+}
+
+#[test]
+fn test_regex_unicode_classes_in_brackets() {
+    let re = Regex::new("[\\pL\\p{Nd}_]+").unwrap();
+
+    assert_eq!(re.find("--été_42--").unwrap().as_str(), "été_42");
+    assert_eq!(
+        Regex::new("[^\\P{Greek}]+").unwrap().find("abc αβγ").unwrap().as_str(),
+        "αβγ"
+    );
+    assert_eq!(Regex::new("\\pL+").unwrap().find("12 héllo").unwrap().as_str(), "héllo");
+}
+
+#[test]
+fn test_unicode_version() {
+    assert_eq!(UNICODE_VERSION, (14, 0, 0));
+}
+
+#[test]
+fn test_regex_case_insensitive_flag() {
+    let re = Regex::new("(?i)hello world").unwrap();
+
+    assert!(re.is_match("HeLLo WoRLD"));
+    assert!(re.is_match("hello world"));
+    assert!(!re.is_match("hello, world"));
+    assert_eq!(re.find("say HELLO WORLD!").unwrap().as_str(), "HELLO WORLD");
+}
+
+#[test]
+fn test_regex_case_insensitive_flag_ends_with_its_group() {
+    let re = Regex::new("a((?i)b)c").unwrap();
+
+    assert!(re.is_match("aBc"));
+    assert!(!re.is_match("ABc"));
+    assert!(!re.is_match("aBC"));
+    assert!(Regex::new("a(?i)b|c").unwrap().is_match("C"));
+}
+
+#[test]
+fn test_regex_case_insensitive_unicode_folding() {
+    let re = RegexBuilder::new("k+ß?σ").case_insensitive(true).build().unwrap();
+
+    assert!(re.is_match("kK\u{212A}Σ"));
+    assert!(re.is_match("Kẞς"));
+    assert!(!re.is_match("kss"));
+    assert!(Regex::new("(?i)ǆ").unwrap().is_match("ǅ"));
+    assert!(Regex::new("(?i)ǆ").unwrap().is_match("Ǆ"));
+}
+
+#[test]
+fn test_regex_case_insensitive_classes() {
+    let re = RegexBuilder::new("[a-c]+[^x]\\p{Lu}")
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+
+    assert!(re.is_match("aBcAy"));
+    assert!(re.is_match("CCcab"));
+    assert!(!re.is_match("abcXy"));
+    assert!(!Regex::new("(?i)[^k]").unwrap().is_match("\u{212A}"));
+    assert!(!Regex::new("(?i)\\P{Ll}").unwrap().is_match("A"));
+}
+
+#[test]
+fn test_regex_scoped_flag_group() {
+    let re = Regex::new("a(?i:b)c").unwrap();
+
+    assert!(re.is_match("aBc"));
+    assert!(!re.is_match("aBC"));
+    assert!(Regex::new("(?i)a(?-i:b)c").unwrap().is_match("AbC"));
+    assert!(!Regex::new("(?i)a(?-i:b)c").unwrap().is_match("ABC"));
+}
+
+#[test]
+fn test_regex_scoped_flag_group_doesnt_capture() {
+    let re = Regex::new("(?i:a)(b)").unwrap();
+    let captures = re.captures("Ab").unwrap();
+
+    assert_eq!(captures.len(), 2);
+    assert_eq!(captures.get(1).unwrap().as_str(), "b");
+}
+
+#[test]
+fn test_regex_dot_doesnt_match_new_line() {
+    assert!(!Regex::new("a.b").unwrap().is_match("a\nb"));
+    assert!(Regex::new("a.b").unwrap().is_match("a\rb"));
+    assert!(Regex::new("(?s)a.b").unwrap().is_match("a\nb"));
+    assert!(Regex::new("a(?s:.)b").unwrap().is_match("a\nb"));
+    assert_eq!(Regex::new(".+").unwrap().find("ab\ncd").unwrap().as_str(), "ab");
+}
+
+#[test]
+fn test_regex_multi_line_flag() {
+    let re = Regex::new("(?m)^[a-z]+$").unwrap();
+    let lines: Vec<_> = re.find_iter("ab\n12\ncd").map(|m| m.as_str()).collect();
+
+    assert_eq!(lines, vec!["ab", "cd"]);
+    assert_eq!(
+        Regex::new("(?m-s:^.+$)").unwrap().find("\nab\n").unwrap().as_str(),
+        "ab"
+    );
+    let multi_line = |pattern| RegexBuilder::new(pattern).multi_line(true).build().unwrap();
+    assert!(multi_line("^b").find("a\nb").is_some());
+    assert_eq!(multi_line("(?-m)^b").find("a\nb"), None);
+}
+
+#[test]
+fn test_regex_verbose_mode() {
+    let re = Regex::new(
+        r"(?x)
+        (?<year>  \d{4} ) -  # the year
+        (?<month> \d{2} ) -  # the month
+        (?<day>   \d{2} )    # the day
+        ",
+    )
+    .unwrap();
+    let captures = re.captures("on 2024-02-29").unwrap();
+
+    assert_eq!(captures.get(0).unwrap().as_str(), "2024-02-29");
+    assert_eq!(captures.name("month").unwrap().as_str(), "02");
+    assert_eq!(captures.name("day").unwrap().as_str(), "29");
+}
+
+#[test]
+fn test_regex_verbose_mode_keeps_escaped_and_class_whitespace() {
+    let re = Regex::new(r"(?x) a\ b [ ] c \# d").unwrap();
+
+    assert!(re.is_match("a b c#d"));
+    assert!(!re.is_match("abc#d"));
+}
+
+#[test]
+fn test_regex_verbose_mode_ends_with_its_group() {
+    let re = Regex::new("((?x) a b ) c").unwrap();
+
+    assert!(re.is_match("ab c"));
+    assert!(!re.is_match("abc"));
+    assert!(Regex::new("(?x: a b ) c").unwrap().is_match("ab c"));
+}
+
+#[test]
+fn test_regex_verbose_mode_error_positions() {
+    assert_eq!(
+        Regex::new("(?x) a  \\q").unwrap_err(),
+        Error::Syntax("Invalid escape sequence: \"\\q\" at position 8 isn't a known escape sequence!".to_string())
+    );
+}
+
+#[test]
+fn test_builder_dot_matches_new_line() {
+    let re = RegexBuilder::new("a.b").dot_matches_new_line(true).build().unwrap();
+
+    assert!(re.is_match("a\nb"));
+    assert!(!RegexBuilder::new("a(?-s:.)b")
+        .dot_matches_new_line(true)
+        .build()
+        .unwrap()
+        .is_match("a\nb"));
+}
+
+#[test]
+fn test_builder_nest_limit() {
+    assert!(RegexBuilder::new("((a))").nest_limit(2).build().is_ok());
+    assert_eq!(
+        RegexBuilder::new("(a(?:b(c)))").nest_limit(2).build().unwrap_err(),
+        Error::Syntax(
+            "Invalid group at position 6: the groups are nested deeper than the nest limit of 2!".to_string()
+        )
+    );
+
+    let deep = format!("{}a{}", "(".repeat(300), ")".repeat(300));
+    assert!(Regex::new(&deep).is_err());
+}
+
+#[test]
+fn test_builder_nest_limit_counts_repetitions() {
+    assert!(RegexBuilder::new("(a?)+").nest_limit(3).build().is_ok());
+    assert!(RegexBuilder::new("(a?)+").nest_limit(2).build().is_err());
+
+    let optionals = format!("a{}", "?".repeat(100_000));
+    assert_eq!(
+        Regex::new(&optionals).unwrap_err(),
+        Error::Syntax(
+            "Invalid repetition at position 251: the groups and repetitions are nested deeper than the nest limit of 250!"
+                .to_string()
+        )
+    );
+}
+
+#[test]
+fn test_long_chains_dont_overflow_the_stack() {
+    let alternation = (b'a'..=b'z')
+        .cycle()
+        .take(20_000)
+        .map(|letter| char::from(letter).to_string())
+        .collect::<Vec<_>>()
+        .join("|");
+    let re = Regex::new(&alternation).unwrap();
+    assert!(re.is_match("z"));
+    assert_eq!(re.find("0z").unwrap().range(), 1..2);
+
+    let literal = "ab".repeat(25_000);
+    let re = Regex::new(&literal).unwrap();
+    assert!(re.is_match(&literal));
+    assert!(!re.is_match(&literal[1..]));
+
+    let class = format!("[{literal}]");
+    assert!(Regex::new(&class).unwrap().is_match("b"));
+}
+
+#[test]
+fn test_builder_nfa_size_limit() {
+    assert!(RegexBuilder::new("a{100}").nfa_size_limit(1000).build().is_ok());
+    assert_eq!(
+        RegexBuilder::new("a{1000}").nfa_size_limit(1000).build().unwrap_err(),
+        Error::TooBig("Regex too big: the NFA exceeds the size limit of 1000 states!".to_string())
+    );
+    assert!(matches!(Regex::new("(a{1000}){1000}"), Err(Error::TooBig(_))));
+
+    // The byte automaton has a state for every byte of the characters, and the limit is checked on it too
+    assert!(RegexBuilder::new("a{100}").nfa_size_limit(1000).build_bytes().is_ok());
+    assert!(RegexBuilder::new("\\w").nfa_size_limit(100).build().is_ok());
+    assert_eq!(
+        RegexBuilder::new("\\w").nfa_size_limit(100).build_bytes().unwrap_err(),
+        Error::TooBig("Regex too big: the NFA exceeds the size limit of 100 states!".to_string())
+    );
+}
+
+#[test]
+fn test_builder_dfa_size_limit() {
+    let re = RegexBuilder::new("(a|b)*a(a|b){4}")
+        .dfa_size_limit(100)
+        .build()
+        .unwrap();
+    assert_eq!(re.engine(), Engine::Dfa);
+
+    let re = RegexBuilder::new("(a|b)*a(a|b){8}")
+        .dfa_size_limit(100)
+        .build()
+        .unwrap();
+    assert_eq!(re.engine(), Engine::Nfa);
+    assert!(re.is_match("bbabbbbbbbb"));
+    assert!(!re.is_match("bbbabbbbbbbbb"));
+}
+
+#[test]
+fn test_regex_falls_back_to_nfa_on_exponential_dfa() {
+    let re = Regex::new("(a|b)*a(a|b){20}").unwrap();
+    let matching = format!("ba{}", "ab".repeat(10));
+
+    assert_eq!(re.engine(), Engine::Nfa);
+    assert!(re.is_match(&matching));
+    assert!(!re.is_match(&format!("a{}", "b".repeat(21))));
+    assert!(!re.is_match("a"));
+    assert_eq!(re.find(&format!("xx{matching}")).unwrap().as_str(), matching);
+    assert_eq!(Regex::new("[a-z]+").unwrap().engine(), Engine::Dfa);
+}
+
+#[test]
+fn test_regex_nfa_engine_with_assertions() {
+    let re = RegexBuilder::new("(?m)^(a|b)*a(a|b){6}$\\n\\b.")
+        .dfa_size_limit(10)
+        .build()
+        .unwrap();
+
+    assert_eq!(re.engine(), Engine::Nfa);
+    assert!(re.is_match("abbbbbb\nc"));
+    assert!(!re.is_match("abbbbbb\n "));
+    assert!(!re.is_match("bbbbbbb\nc"));
+}
+
+#[test]
+fn test_builder_hopcroft_minimization() {
+    for pattern in ["(a|b)*abb", "[a-z]+@[a-z]+\\.(com|org)", "(?m)^\\w+\\b$", "x*"] {
+        let brzozowski = Regex::new(pattern).unwrap();
+        let hopcroft = RegexBuilder::new(pattern)
+            .minimization(Minimization::Hopcroft)
+            .build()
+            .unwrap();
+
+        for text in [
+            "abb",
+            "babb",
+            "ab",
+            "me@example.com",
+            "me@example.net",
+            "word",
+            "two words",
+            "",
+            "xxx",
+        ] {
+            assert_eq!(brzozowski.is_match(text), hopcroft.is_match(text));
+        }
+    }
+}
+
+#[test]
+fn test_builder_hopcroft_avoids_exponential_reversed_dfa() {
+    // The reversed regex is `(a|b)*a(a|b){20}`, its DFA is exponential but the DFA of the regex isn't
+    let pattern = "(a|b){20}a(a|b)*";
+    let matching = format!("{}a", "b".repeat(20));
+
+    assert_eq!(Regex::new(pattern).unwrap().engine(), Engine::Nfa);
+
+    let re = RegexBuilder::new(pattern)
+        .minimization(Minimization::Hopcroft)
+        .build()
+        .unwrap();
+    assert_eq!(re.engine(), Engine::Dfa);
+    assert!(re.is_match(&matching));
+    assert!(re.is_match(&format!("{matching}ab")));
+    assert!(!re.is_match(&"b".repeat(25)));
+}
+
+#[test]
+fn test_builder_lazy_dfa() {
+    let re = RegexBuilder::new("(a|b)*a(a|b){20}").lazy_dfa(true).build().unwrap();
+    let matching = format!("ba{}", "ab".repeat(10));
+
+    assert_eq!(re.engine(), Engine::LazyDfa);
+    assert!(re.is_match(&matching));
+    assert!(!re.is_match(&format!("a{}", "b".repeat(21))));
+    assert_eq!(re.find(&format!("xx{matching}")).unwrap().as_str(), matching);
+}
+
+#[test]
+fn test_builder_lazy_dfa_with_small_cache() {
+    let re = RegexBuilder::new("(?i)^[a-z]+@[a-z]+\\.(com|org)$")
+        .lazy_dfa(true)
+        .dfa_size_limit(3)
+        .build()
+        .unwrap();
+
+    assert_eq!(re.engine(), Engine::LazyDfa);
+    assert!(re.is_match("Me@Example.COM"));
+    assert!(re.is_match("me@example.org"));
+    assert!(!re.is_match("me@example.net"));
+    assert!(!re.is_match("me@.com"));
+}
+
+#[test]
+fn test_bytes_regex_is_match() {
+    let re = bytes::Regex::new("caf[eé]\\p{Greek}+").unwrap();
+
+    assert!(re.is_match("caféαβγ".as_bytes()));
+    assert!(re.is_match("cafeω".as_bytes()));
+    assert!(!re.is_match(b"caf\xE9\xCE\xB1"));
+    assert!(!re.is_match("cafeαβ".as_bytes().split_last().unwrap().1));
+    assert_eq!(re.engine(), Engine::Dfa);
+}
+
+#[test]
+fn test_bytes_regex_find_in_invalid_utf8() {
+    let re = bytes::Regex::new("[0-9]+\\.[0-9]+").unwrap();
+    let haystack = b"\xFF\xFEversion\x00\x80 12.5\xC3";
+
+    let found = re.find(haystack).unwrap();
+    assert_eq!(found.as_bytes(), b"12.5");
+    assert_eq!(found.range(), 12..16);
+    assert!(re.find(b"\xFF\xFF.").is_none());
+}
+
+#[test]
+fn test_bytes_regex_dot_matches_any_byte() {
+    let re = bytes::Regex::new("a.b").unwrap();
+    assert!(re.is_match("aéb".as_bytes()));
+    assert!(!re.is_match(b"a\xFFb"));
+
+    let re = RegexBuilder::new("a.b")
+        .dot_matches_any_byte(true)
+        .build_bytes()
+        .unwrap();
+    assert!(re.is_match(b"a\xFFb"));
+    assert!(re.is_match(b"a\x00b"));
+    assert!(!re.is_match(b"a\nb"));
+    assert!(!re.is_match("aéb".as_bytes()));
+    assert_eq!(re.find(b"\xC3a\xC3b").unwrap().range(), 1..4);
+
+    let re = RegexBuilder::new("(?s)a.b")
+        .dot_matches_any_byte(true)
+        .build_bytes()
+        .unwrap();
+    assert!(re.is_match(b"a\nb"));
+
+    // The regex of `&str` can't match invalid UTF-8, so the option doesn't change it
+    let re = RegexBuilder::new("a.b").dot_matches_any_byte(true).build().unwrap();
+    assert!(re.is_match("aéb"));
+}
+
+#[test]
+fn test_bytes_regex_assertions() {
+    let re = bytes::Regex::new("\\bλ\\w*\\b").unwrap();
+    assert_eq!(re.find("x λόγος y".as_bytes()).unwrap().as_bytes(), "λόγος".as_bytes());
+    assert!(re.find("xλόγος".as_bytes()).is_none());
+    assert_eq!(re.find(b"\xFF\xCE\xBB\xFF").unwrap().range(), 1..3);
+
+    // The assertions don't match between the bytes of a character, only around the invalid bytes
+    let re = bytes::Regex::new("\\B").unwrap();
+    assert!(re.find("é".as_bytes()).is_none());
+    assert_eq!(re.find(b"\xC3\xA9\xFF").unwrap().range(), 3..3);
+    assert_eq!(re.find(b"a\xC3\xC3").unwrap().range(), 2..2);
+    let re = RegexBuilder::new("(?s).\\B.")
+        .dot_matches_any_byte(true)
+        .build_bytes()
+        .unwrap();
+    assert!(re.find("é".as_bytes()).is_none());
+    assert!(!re.is_match("é".as_bytes()));
+    assert!(re.is_match(b"\xC3\xC3"));
+    assert_eq!(re.engine(), Engine::Nfa);
+
+    let re = RegexBuilder::new("(?m)^\\w+$\\n.*")
+        .dot_matches_any_byte(true)
+        .build_bytes()
+        .unwrap();
+    assert!(re.is_match(b"line\n\xFF\xFE"));
+    assert!(!re.is_match(b"line\xFF\n"));
+}
+
+#[test]
+fn test_bytes_regex_engines() {
+    let texts: [&[u8]; 4] = [b"abab", "αβ".as_bytes(), b"a\xFFb", b""];
+
+    for pattern in ["(a|b)*", "\\w+", "a.b", "[^a]+"] {
+        let dfa = RegexBuilder::new(pattern)
+            .dot_matches_any_byte(true)
+            .build_bytes()
+            .unwrap();
+        let nfa = RegexBuilder::new(pattern)
+            .dot_matches_any_byte(true)
+            .dfa_size_limit(0)
+            .build_bytes()
+            .unwrap();
+        let lazy_dfa = RegexBuilder::new(pattern)
+            .dot_matches_any_byte(true)
+            .lazy_dfa(true)
+            .build_bytes()
+            .unwrap();
+
+        assert_eq!(nfa.engine(), Engine::Nfa);
+        assert_eq!(lazy_dfa.engine(), Engine::LazyDfa);
+        for text in texts {
+            assert_eq!(dfa.is_match(text), nfa.is_match(text));
+            assert_eq!(dfa.is_match(text), lazy_dfa.is_match(text));
+        }
+    }
+}